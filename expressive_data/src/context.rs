@@ -0,0 +1,211 @@
+//! Traits to use Rust values as the variables of an expression. With the `derive` feature
+//! `#[derive(ExpressiveContext)]` implements them for structs.
+
+use crate::money::Money;
+use crate::numeric::NumericValue;
+use crate::unit::Quantity;
+use crate::value::Value;
+use bigdecimal::num_bigint::BigInt;
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap};
+
+#[cfg(feature = "derive")]
+pub use expressive_derive::ExpressiveContext;
+
+/// A value that provides the variables for an expression.
+pub trait ExpressiveContext {
+    fn variables(&self) -> HashMap<String, Value>;
+}
+
+/// A Rust value that converts to a value of an expression. `None` converts to null, and floats that
+/// are not finite convert to null as well.
+pub trait ToValue {
+    fn to_value(&self) -> Value;
+}
+
+impl ToValue for Value {
+    fn to_value(&self) -> Value {
+        self.clone()
+    }
+}
+
+impl ToValue for bool {
+    fn to_value(&self) -> Value {
+        Value::Boolean(*self)
+    }
+}
+
+macro_rules! integer_to_value {
+    ($($integer:ty),*) => {
+        $(
+            impl ToValue for $integer {
+                fn to_value(&self) -> Value {
+                    Value::Integer(BigInt::from(*self))
+                }
+            }
+        )*
+    };
+}
+
+integer_to_value!(i8, i16, i32, i64, i128, u8, u16, u32, u64, u128, usize);
+
+impl ToValue for f32 {
+    fn to_value(&self) -> Value {
+        Value::try_from(f64::from(*self)).unwrap_or(Value::Null)
+    }
+}
+
+impl ToValue for f64 {
+    fn to_value(&self) -> Value {
+        Value::try_from(*self).unwrap_or(Value::Null)
+    }
+}
+
+impl ToValue for str {
+    fn to_value(&self) -> Value {
+        Value::String(self.to_owned())
+    }
+}
+
+impl ToValue for String {
+    fn to_value(&self) -> Value {
+        Value::String(self.clone())
+    }
+}
+
+impl ToValue for BigDecimal {
+    fn to_value(&self) -> Value {
+        Value::Decimal(self.clone())
+    }
+}
+
+impl ToValue for BigInt {
+    fn to_value(&self) -> Value {
+        Value::Integer(self.clone())
+    }
+}
+
+impl ToValue for NumericValue {
+    fn to_value(&self) -> Value {
+        Value::from(self.clone())
+    }
+}
+
+impl ToValue for DateTime<Utc> {
+    fn to_value(&self) -> Value {
+        Value::DateTime(*self)
+    }
+}
+
+impl ToValue for Money {
+    fn to_value(&self) -> Value {
+        Value::from(self.clone())
+    }
+}
+
+impl ToValue for Quantity {
+    fn to_value(&self) -> Value {
+        Value::from(self.clone())
+    }
+}
+
+impl<T: ToValue + ?Sized> ToValue for &T {
+    fn to_value(&self) -> Value {
+        (**self).to_value()
+    }
+}
+
+impl<T: ToValue + ?Sized> ToValue for Box<T> {
+    fn to_value(&self) -> Value {
+        (**self).to_value()
+    }
+}
+
+impl<T: ToValue> ToValue for Option<T> {
+    fn to_value(&self) -> Value {
+        match self {
+            Some(value) => value.to_value(),
+            None => Value::Null,
+        }
+    }
+}
+
+impl<T: ToValue> ToValue for [T] {
+    fn to_value(&self) -> Value {
+        Value::List(self.iter().map(ToValue::to_value).collect())
+    }
+}
+
+impl<T: ToValue> ToValue for Vec<T> {
+    fn to_value(&self) -> Value {
+        self.as_slice().to_value()
+    }
+}
+
+impl<T: ToValue> ToValue for BTreeMap<String, T> {
+    fn to_value(&self) -> Value {
+        Value::Map(
+            self.iter()
+                .map(|(name, value)| (name.clone(), value.to_value()))
+                .collect(),
+        )
+    }
+}
+
+impl<T: ToValue> ToValue for HashMap<String, T> {
+    fn to_value(&self) -> Value {
+        Value::Map(
+            self.iter()
+                .map(|(name, value)| (name.clone(), value.to_value()))
+                .collect(),
+        )
+    }
+}
+
+impl<T: ToValue> ExpressiveContext for HashMap<String, T> {
+    fn variables(&self) -> HashMap<String, Value> {
+        self.iter()
+            .map(|(name, value)| (name.clone(), value.to_value()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::money::Currency;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_scalars_to_value() {
+        assert_eq!(true.to_value(), Value::Boolean(true));
+        assert_eq!((-3_i8).to_value(), Value::Integer(BigInt::from(-3)));
+        assert_eq!(
+            u128::MAX.to_value(),
+            Value::Integer(BigInt::from(u128::MAX))
+        );
+        assert_eq!(7_usize.to_value(), Value::Integer(BigInt::from(7)));
+        assert_eq!(1.5_f64.to_value().to_string(), "1.5");
+        assert_eq!(f32::NAN.to_value(), Value::Null);
+        assert_eq!("a".to_value(), Value::String("a".to_string()));
+        let amount = Money::new(BigDecimal::from(5), Currency::from_code("EUR").unwrap());
+        assert_eq!(amount.to_value().to_string(), "EUR 5.00");
+        let now = DateTime::<Utc>::from_str("2024-02-29T12:30:00Z").unwrap();
+        assert_eq!(now.to_value(), Value::DateTime(now));
+    }
+
+    #[test]
+    fn test_containers_to_value() {
+        assert_eq!(None::<i32>.to_value(), Value::Null);
+        assert_eq!(
+            Some(Box::new(2_u8)).to_value(),
+            Value::Integer(BigInt::from(2))
+        );
+        assert_eq!(vec![1_i32, 2].to_value().to_string(), "[1, 2]");
+        let fields = BTreeMap::from([("b".to_string(), 2_u16), ("a".to_string(), 1_u16)]);
+        assert_eq!(fields.to_value().to_string(), "{a: 1, b: 2}");
+        let variables = HashMap::from([("x".to_string(), Some("y"))]).variables();
+        assert_eq!(variables["x"], Value::String("y".to_string()));
+    }
+}
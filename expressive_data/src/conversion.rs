@@ -0,0 +1,293 @@
+//! Conversions between values, numbers and Rust types. Conversions that cannot be done exactly fail
+//! with a `LossyConversion` error that says what would be lost.
+
+use crate::error::DataError;
+use crate::numeric::NumericValue;
+use crate::value::Value;
+use bigdecimal::num_bigint::BigInt;
+use bigdecimal::{BigDecimal, ToPrimitive};
+use chrono::{DateTime, Utc};
+use std::str::FromStr;
+
+fn lossy(value: &impl ToString, type_name: &str, loss: &str) -> DataError {
+    DataError::LossyConversion {
+        value: value.to_string(),
+        type_name: type_name.to_owned(),
+        loss: loss.to_owned(),
+    }
+}
+
+fn incompatible(value: &Value, type_name: &str) -> DataError {
+    DataError::StringConversionError {
+        value: value.to_string(),
+        type_name: type_name.to_owned(),
+    }
+}
+
+// A float converts to the shortest decimal that reads back as the same float, so `0.1` becomes
+// exactly `0.1`.
+fn decimal_from_f64(value: f64) -> Result<BigDecimal, DataError> {
+    match value.is_finite() {
+        true => BigDecimal::from_str(&value.to_string())
+            .map_err(|_| lossy(&value, "decimal", "the value")),
+        false => Err(lossy(
+            &value,
+            "decimal",
+            "the value, it is not a finite number",
+        )),
+    }
+}
+
+fn integer_part(value: &NumericValue, type_name: &str) -> Result<BigInt, DataError> {
+    match value {
+        NumericValue::Integer(i) => Ok(i.clone()),
+        NumericValue::Decimal(d) if d.is_integer() => {
+            Ok(d.with_scale(0).into_bigint_and_exponent().0)
+        }
+        NumericValue::Decimal(d) => Err(lossy(d, type_name, "the fractional part")),
+    }
+}
+
+macro_rules! integer_conversions {
+    ($($integer:ty),*) => {
+        $(
+            impl From<$integer> for NumericValue {
+                fn from(value: $integer) -> Self {
+                    NumericValue::Integer(BigInt::from(value))
+                }
+            }
+
+            impl From<$integer> for Value {
+                fn from(value: $integer) -> Self {
+                    Value::Integer(BigInt::from(value))
+                }
+            }
+
+            impl TryFrom<NumericValue> for $integer {
+                type Error = DataError;
+
+                fn try_from(value: NumericValue) -> Result<Self, Self::Error> {
+                    let type_name = stringify!($integer);
+                    let integer = integer_part(&value, type_name)?;
+                    <$integer>::try_from(&integer)
+                        .map_err(|_| lossy(&value, type_name, "the magnitude, it is out of range"))
+                }
+            }
+
+            impl TryFrom<Value> for $integer {
+                type Error = DataError;
+
+                fn try_from(value: Value) -> Result<Self, Self::Error> {
+                    NumericValue::try_from(value)?.try_into()
+                }
+            }
+        )*
+    };
+}
+
+integer_conversions!(i64, u64, i128);
+
+impl TryFrom<f64> for NumericValue {
+    type Error = DataError;
+
+    fn try_from(value: f64) -> Result<Self, Self::Error> {
+        Ok(NumericValue::Decimal(decimal_from_f64(value)?))
+    }
+}
+
+impl TryFrom<f64> for Value {
+    type Error = DataError;
+
+    fn try_from(value: f64) -> Result<Self, Self::Error> {
+        Ok(Value::Decimal(decimal_from_f64(value)?))
+    }
+}
+
+// Only numbers that a float represents exactly convert, e.g. `0.1` does but
+// `0.12345678901234567890` does not.
+impl TryFrom<NumericValue> for f64 {
+    type Error = DataError;
+
+    fn try_from(value: NumericValue) -> Result<Self, Self::Error> {
+        let float = match &value {
+            NumericValue::Decimal(d) => d.to_f64(),
+            NumericValue::Integer(i) => i.to_f64(),
+        }
+        .filter(|float| float.is_finite())
+        .ok_or_else(|| lossy(&value, "f64", "the magnitude, it is out of range"))?;
+        match decimal_from_f64(float)? == BigDecimal::from(value.clone()) {
+            true => Ok(float),
+            false => Err(lossy(&value, "f64", "precision")),
+        }
+    }
+}
+
+impl TryFrom<Value> for f64 {
+    type Error = DataError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        NumericValue::try_from(value)?.try_into()
+    }
+}
+
+impl From<BigDecimal> for NumericValue {
+    fn from(value: BigDecimal) -> Self {
+        NumericValue::Decimal(value)
+    }
+}
+
+impl From<NumericValue> for BigDecimal {
+    fn from(value: NumericValue) -> Self {
+        match value {
+            NumericValue::Decimal(d) => d,
+            NumericValue::Integer(i) => BigDecimal::from(i),
+        }
+    }
+}
+
+impl From<BigDecimal> for Value {
+    fn from(value: BigDecimal) -> Self {
+        Value::Decimal(value)
+    }
+}
+
+impl TryFrom<Value> for BigDecimal {
+    type Error = DataError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        Ok(NumericValue::try_from(value)?.into())
+    }
+}
+
+impl TryFrom<Value> for NumericValue {
+    type Error = DataError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Decimal(d) => Ok(NumericValue::Decimal(d)),
+            Value::Integer(i) => Ok(NumericValue::Integer(i)),
+            value => Err(incompatible(&value, "number")),
+        }
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Boolean(value)
+    }
+}
+
+impl TryFrom<Value> for bool {
+    type Error = DataError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Boolean(b) => Ok(b),
+            value => Err(incompatible(&value, "bool")),
+        }
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::String(value.to_owned())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::String(value)
+    }
+}
+
+impl<'a> TryFrom<&'a Value> for &'a str {
+    type Error = DataError;
+
+    fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::String(s) => Ok(s),
+            value => Err(incompatible(value, "str")),
+        }
+    }
+}
+
+impl TryFrom<Value> for String {
+    type Error = DataError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::String(s) => Ok(s),
+            value => Err(incompatible(&value, "String")),
+        }
+    }
+}
+
+impl From<DateTime<Utc>> for Value {
+    fn from(value: DateTime<Utc>) -> Self {
+        Value::DateTime(value)
+    }
+}
+
+impl TryFrom<Value> for DateTime<Utc> {
+    type Error = DataError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::DateTime(d) => Ok(d),
+            value => Err(incompatible(&value, "DateTime")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_integers() {
+        assert_eq!(Value::from(42i64), Value::Integer(BigInt::from(42)));
+        assert_eq!(i64::try_from(Value::from(u64::MAX as i128)).ok(), None);
+        assert_eq!(u64::try_from(Value::from(u64::MAX)).unwrap(), u64::MAX);
+        let two = Value::Decimal(BigDecimal::from_str("2.00").unwrap());
+        assert_eq!(i128::try_from(two).unwrap(), 2);
+        let result = i64::try_from(Value::Decimal(BigDecimal::from_str("2.5").unwrap()));
+        assert!(matches!(
+            result,
+            Err(DataError::LossyConversion { loss, .. }) if loss == "the fractional part"
+        ));
+        assert!(matches!(
+            u64::try_from(Value::from(-1i64)),
+            Err(DataError::LossyConversion { .. })
+        ));
+        assert!(i64::try_from(Value::from("2")).is_err());
+    }
+
+    #[test]
+    fn test_floats() {
+        let value = Value::try_from(0.1).unwrap();
+        assert_eq!(value, Value::Decimal(BigDecimal::from_str("0.1").unwrap()));
+        assert_eq!(f64::try_from(value).unwrap(), 0.1);
+        assert!(Value::try_from(f64::NAN).is_err());
+        let precise = NumericValue::try_from("0.12345678901234567890123").unwrap();
+        assert!(matches!(
+            f64::try_from(precise),
+            Err(DataError::LossyConversion { loss, .. }) if loss == "precision"
+        ));
+    }
+
+    #[test]
+    fn test_other_types() {
+        assert!(bool::try_from(Value::from(true)).unwrap());
+        let value = Value::from("text");
+        assert_eq!(<&str>::try_from(&value).unwrap(), "text");
+        assert_eq!(String::try_from(value).unwrap(), "text");
+        let now = Utc::now();
+        assert_eq!(DateTime::<Utc>::try_from(Value::from(now)).unwrap(), now);
+        let decimal = BigDecimal::try_from(Value::from(3u64)).unwrap();
+        assert_eq!(decimal, BigDecimal::from(3));
+        assert!(matches!(
+            String::try_from(Value::Null),
+            Err(DataError::StringConversionError { .. })
+        ));
+    }
+}
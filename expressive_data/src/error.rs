@@ -3,5 +3,37 @@ use thiserror::Error;
 #[derive(Debug, Error)]
 pub enum DataError {
     #[error("could not convert the value '{value:}' to type '{type_name:}'")]
-    StringConversionError{ value: String, type_name: String },
+    StringConversionError { value: String, type_name: String },
+    #[error("converting '{value:}' to '{type_name:}' would lose {loss:}")]
+    LossyConversion {
+        value: String,
+        type_name: String,
+        loss: String,
+    },
+    #[error("the locale '{tag:}' is not a known locale")]
+    UnknownLocale { tag: String },
+    #[error("the currency code '{code:}' is not a known ISO-4217 currency")]
+    UnknownCurrency { code: String },
+    #[error("cannot combine amounts in currency '{left:}' with amounts in currency '{right:}'")]
+    CurrencyMismatch { left: String, right: String },
+    #[error("the operation '{operation:}' is not supported between '{left:}' and '{right:}'")]
+    UnsupportedOperation {
+        operation: String,
+        left: String,
+        right: String,
+    },
+    #[error("the unit '{symbol:}' is not a known unit of measure")]
+    UnknownUnit { symbol: String },
+    #[error("the unit '{symbol:}' must have a positive factor instead got {factor:}")]
+    InvalidUnitFactor { symbol: String, factor: String },
+    #[error("the units '{left:}' and '{right:}' have different dimensions")]
+    DimensionMismatch { left: String, right: String },
+    #[error("'{from:}' cannot be converted to '{to:}' exactly, a rounding scale is required")]
+    InexactConversion { from: String, to: String },
+    #[error("the exponent {exponent:} is larger than the maximum of {max:}")]
+    ExponentTooLarge { exponent: String, max: u32 },
+    #[error("division by zero")]
+    DivisionByZero,
+    #[error("could not allocate: {message:}")]
+    AllocationError { message: String },
 }
@@ -0,0 +1,130 @@
+//! Conversions between JSON documents and values. Numbers are converted through their text so that
+//! big integers and decimals keep their precision.
+
+use crate::value::Value;
+use bigdecimal::num_bigint::BigInt;
+use bigdecimal::BigDecimal;
+use chrono::SecondsFormat;
+use serde_json::{Map, Number};
+use std::str::FromStr;
+
+impl From<serde_json::Value> for Value {
+    fn from(value: serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Null => Value::Null,
+            serde_json::Value::Bool(b) => Value::Boolean(b),
+            serde_json::Value::Number(n) => from_number(&n),
+            serde_json::Value::String(s) => Value::String(s),
+            serde_json::Value::Array(items) => {
+                Value::List(items.into_iter().map(Value::from).collect())
+            }
+            serde_json::Value::Object(fields) => Value::Map(
+                fields
+                    .into_iter()
+                    .map(|(name, value)| (name, Value::from(value)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+// Numbers without a fraction or exponent are integers, any other number is a decimal.
+fn from_number(number: &Number) -> Value {
+    let text = number.to_string();
+    if let Ok(i) = BigInt::from_str(&text) {
+        return Value::Integer(i);
+    }
+    match BigDecimal::from_str(&text) {
+        Ok(d) => Value::Decimal(d),
+        Err(_) => Value::String(text),
+    }
+}
+
+fn to_number(text: String) -> serde_json::Value {
+    match Number::from_str(&text) {
+        Ok(number) => serde_json::Value::Number(number),
+        Err(_) => serde_json::Value::String(text),
+    }
+}
+
+// Money and quantities become objects with their amount and currency or magnitude and unit, and
+// date times become RFC 3339 strings.
+impl From<Value> for serde_json::Value {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Boolean(b) => serde_json::Value::Bool(b),
+            Value::DateTime(d) => {
+                serde_json::Value::String(d.to_rfc3339_opts(SecondsFormat::AutoSi, true))
+            }
+            Value::Decimal(d) => to_number(d.to_string()),
+            Value::Integer(i) => to_number(i.to_string()),
+            Value::List(items) => {
+                serde_json::Value::Array(items.into_iter().map(serde_json::Value::from).collect())
+            }
+            Value::Map(fields) => serde_json::Value::Object(
+                fields
+                    .into_iter()
+                    .map(|(name, value)| (name, serde_json::Value::from(value)))
+                    .collect(),
+            ),
+            Value::Money { amount, currency } => {
+                let mut fields = Map::new();
+                fields.insert("amount".to_owned(), to_number(amount.to_string()));
+                fields.insert("currency".to_owned(), currency.code().into());
+                serde_json::Value::Object(fields)
+            }
+            Value::Null => serde_json::Value::Null,
+            Value::Quantity { magnitude, unit } => {
+                let mut fields = Map::new();
+                fields.insert("magnitude".to_owned(), to_number(magnitude.to_string()));
+                fields.insert("unit".to_owned(), unit.symbol().into());
+                serde_json::Value::Object(fields)
+            }
+            Value::String(s) => serde_json::Value::String(s),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::money::{Currency, Money};
+    use serde_json::json;
+
+    #[test]
+    fn test_numbers_are_lossless() {
+        let document: serde_json::Value =
+            serde_json::from_str(r#"[123456789012345678901234567890, 0.1, 1.50, 2e3, -7]"#)
+                .unwrap();
+        let value = Value::from(document.clone());
+        assert_eq!(
+            value,
+            Value::List(vec![
+                Value::Integer(BigInt::from_str("123456789012345678901234567890").unwrap()),
+                Value::Decimal(BigDecimal::from_str("0.1").unwrap()),
+                Value::Decimal(BigDecimal::from_str("1.50").unwrap()),
+                Value::Decimal(BigDecimal::from_str("2e3").unwrap()),
+                Value::Integer(BigInt::from(-7)),
+            ])
+        );
+        assert_eq!(
+            serde_json::to_string(&serde_json::Value::from(value)).unwrap(),
+            "[123456789012345678901234567890,0.1,1.50,2000,-7]"
+        );
+    }
+
+    #[test]
+    fn test_objects() {
+        let document = json!({"customer": {"name": "Ada", "vip": true, "discount": null}});
+        let value = Value::from(document.clone());
+        assert_eq!(serde_json::Value::from(value), document);
+        let money = Value::from(Money::new(
+            BigDecimal::from_str("12.5").unwrap(),
+            Currency::from_code("EUR").unwrap(),
+        ));
+        assert_eq!(
+            serde_json::to_string(&serde_json::Value::from(money)).unwrap(),
+            r#"{"amount":12.50,"currency":"EUR"}"#
+        );
+    }
+}
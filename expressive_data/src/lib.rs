@@ -1,3 +1,12 @@
-pub mod value;
+pub mod context;
+mod conversion;
+pub mod error;
+#[cfg(feature = "json")]
+mod json;
+pub mod locale;
+pub mod money;
 pub mod numeric;
-mod error;
+#[cfg(feature = "serde")]
+mod serialization;
+pub mod unit;
+pub mod value;
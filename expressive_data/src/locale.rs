@@ -0,0 +1,415 @@
+use crate::error::DataError;
+use crate::money::{Currency, Money};
+use crate::numeric::NumericValue;
+use bigdecimal::{BigDecimal, RoundingMode};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SymbolPosition {
+    Prefix,
+    PrefixSpaced,
+    SuffixSpaced,
+}
+
+// Tag, decimal separator, grouping separator, currency symbol position and date pattern.
+const LOCALES: &[(&str, char, char, SymbolPosition, &str)] = &[
+    (
+        "de-CH",
+        '.',
+        '\'',
+        SymbolPosition::PrefixSpaced,
+        "dd.MM.yyyy",
+    ),
+    (
+        "de-DE",
+        ',',
+        '.',
+        SymbolPosition::SuffixSpaced,
+        "dd.MM.yyyy",
+    ),
+    ("en-GB", '.', ',', SymbolPosition::Prefix, "dd/MM/yyyy"),
+    ("en-US", '.', ',', SymbolPosition::Prefix, "MM/dd/yyyy"),
+    (
+        "fr-FR",
+        ',',
+        '\u{202f}',
+        SymbolPosition::SuffixSpaced,
+        "dd/MM/yyyy",
+    ),
+    (
+        "nl-BE",
+        ',',
+        '.',
+        SymbolPosition::PrefixSpaced,
+        "dd/MM/yyyy",
+    ),
+    (
+        "nl-NL",
+        ',',
+        '.',
+        SymbolPosition::PrefixSpaced,
+        "dd-MM-yyyy",
+    ),
+];
+
+// The language only tags resolve to the first locale for the language in this list.
+const DEFAULT_REGIONS: &[(&str, &str)] = &[
+    ("de", "de-DE"),
+    ("en", "en-US"),
+    ("fr", "fr-FR"),
+    ("nl", "nl-NL"),
+];
+
+const CURRENCY_SYMBOLS: &[(&str, &str)] = &[
+    ("€", "EUR"),
+    ("$", "USD"),
+    ("£", "GBP"),
+    ("¥", "JPY"),
+    ("₹", "INR"),
+];
+
+/// The conventions to write numbers, amounts of money and dates in, e.g. `1.234,56` and
+/// `31-12-2024` in the Netherlands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Locale {
+    tag: &'static str,
+    decimal_separator: char,
+    grouping_separator: char,
+    symbol_position: SymbolPosition,
+    date_pattern: &'static str,
+}
+
+impl Locale {
+    /// Finds a locale by its language tag, e.g. `nl-NL`, `de_DE` or just `de`.
+    pub fn from_tag(tag: &str) -> Result<Locale, DataError> {
+        let normalized = tag.replace('_', "-").to_lowercase();
+        let full_tag = DEFAULT_REGIONS
+            .iter()
+            .find(|(language, _)| *language == normalized)
+            .map(|(_, full_tag)| full_tag.to_lowercase())
+            .unwrap_or(normalized);
+        LOCALES
+            .iter()
+            .find(|(known_tag, ..)| known_tag.to_lowercase() == full_tag)
+            .map(
+                |(tag, decimal_separator, grouping_separator, symbol_position, date_pattern)| {
+                    Locale {
+                        tag,
+                        decimal_separator: *decimal_separator,
+                        grouping_separator: *grouping_separator,
+                        symbol_position: *symbol_position,
+                        date_pattern,
+                    }
+                },
+            )
+            .ok_or(DataError::UnknownLocale {
+                tag: tag.to_string(),
+            })
+    }
+
+    pub fn tag(&self) -> &'static str {
+        self.tag
+    }
+
+    pub fn decimal_separator(&self) -> char {
+        self.decimal_separator
+    }
+
+    pub fn grouping_separator(&self) -> char {
+        self.grouping_separator
+    }
+
+    /// The pattern dates are usually written in, e.g. `dd-MM-yyyy`.
+    pub fn date_pattern(&self) -> &'static str {
+        self.date_pattern
+    }
+
+    /// Parses a number written with the separators of this locale. Grouping separators are
+    /// optional, but when they are used they must separate groups of three digits.
+    pub fn parse_number(&self, text: &str) -> Result<NumericValue, DataError> {
+        let invalid = || DataError::StringConversionError {
+            value: text.to_string(),
+            type_name: format!("number ({})", self.tag),
+        };
+        let trimmed = text.trim();
+        let (sign, unsigned) = match trimmed.strip_prefix('-') {
+            Some(rest) => ("-", rest),
+            None => ("", trimmed.strip_prefix('+').unwrap_or(trimmed)),
+        };
+        let (whole, fraction) = match unsigned.split_once(self.decimal_separator) {
+            Some((whole, fraction)) => (whole, Some(fraction)),
+            None => (unsigned, None),
+        };
+        let groups: Vec<&str> = whole
+            .split(|c| c == self.grouping_separator || (self.is_space_grouped() && is_space(c)))
+            .collect();
+        let valid_groups = groups.iter().enumerate().all(|(index, group)| {
+            let digits = group.chars().all(|c| c.is_ascii_digit());
+            match (index, groups.len()) {
+                (_, 1) => digits && !group.is_empty(),
+                (0, _) => digits && (1..=3).contains(&group.len()),
+                _ => digits && group.len() == 3,
+            }
+        });
+        let valid_fraction = fraction
+            .map(|f| !f.is_empty() && f.chars().all(|c| c.is_ascii_digit()))
+            .unwrap_or(true);
+        if !valid_groups || !valid_fraction {
+            return Err(invalid());
+        }
+        let plain = match fraction {
+            Some(fraction) => format!("{}{}.{}", sign, groups.concat(), fraction),
+            None => format!("{}{}", sign, groups.concat()),
+        };
+        NumericValue::try_from(plain).map_err(|_| invalid())
+    }
+
+    /// Formats a number with the separators of this locale, rounded half to even to the given
+    /// number of decimal places if there is one.
+    pub fn format_number(&self, number: &NumericValue, scale: Option<i64>) -> String {
+        let decimal = match number {
+            NumericValue::Decimal(d) => d.clone(),
+            NumericValue::Integer(i) => BigDecimal::from(i.clone()),
+        };
+        let decimal = match scale {
+            Some(scale) => decimal.with_scale_round(scale, RoundingMode::HalfEven),
+            None => decimal,
+        };
+        self.format_decimal(&decimal)
+    }
+
+    /// Parses an amount of money written with a currency symbol, e.g. `€ 1.234,56`, or with an
+    /// ISO-4217 code, e.g. `1.234,56 EUR`.
+    pub fn parse_money(&self, text: &str) -> Result<Money, DataError> {
+        let trimmed = text.trim();
+        let (sign, trimmed) = match trimmed.strip_prefix('-') {
+            Some(rest) => ("-", rest.trim_start()),
+            None => ("", trimmed),
+        };
+        let symbols = CURRENCY_SYMBOLS
+            .iter()
+            .map(|(symbol, code)| (*symbol, *code));
+        let codes = trimmed
+            .split(|c: char| !c.is_ascii_alphabetic())
+            .filter(|word| word.len() == 3)
+            .filter_map(|word| Currency::from_code(word).ok())
+            .map(|currency| (currency.code(), currency.code()));
+        let (symbol, code) = symbols
+            .chain(codes)
+            .find(|(symbol, _)| trimmed.starts_with(symbol) || trimmed.ends_with(symbol))
+            .ok_or(DataError::StringConversionError {
+                value: text.to_string(),
+                type_name: format!("money ({})", self.tag),
+            })?;
+        let amount = trimmed
+            .strip_prefix(symbol)
+            .or_else(|| trimmed.strip_suffix(symbol))
+            .unwrap_or(trimmed);
+        let amount = match self.parse_number(&format!("{}{}", sign, amount.trim()))? {
+            NumericValue::Decimal(d) => d,
+            NumericValue::Integer(i) => BigDecimal::from(i),
+        };
+        Ok(Money::new(amount, Currency::from_code(code)?))
+    }
+
+    /// Formats an amount of money with its currency symbol, or its code when it has no symbol.
+    pub fn format_money(&self, money: &Money) -> String {
+        let code = money.currency().code();
+        let symbol = CURRENCY_SYMBOLS
+            .iter()
+            .find(|(_, symbol_code)| *symbol_code == code)
+            .map(|(symbol, _)| *symbol)
+            .unwrap_or(code);
+        let amount = self.format_decimal(money.amount());
+        let (sign, amount) = match amount.strip_prefix('-') {
+            Some(amount) => ("-", amount.to_string()),
+            None => ("", amount),
+        };
+        match (self.symbol_position, symbol == code) {
+            (SymbolPosition::Prefix, false) => format!("{}{}{}", sign, symbol, amount),
+            (SymbolPosition::SuffixSpaced, _) => format!("{}{} {}", sign, amount, symbol),
+            _ => format!("{}{} {}", sign, symbol, amount),
+        }
+    }
+
+    /// Parses a date, or a date and time, written in the given pattern or the usual pattern of this
+    /// locale. Patterns use `d`, `M`, `y`, `H`, `m` and `s`, e.g. `dd-MM-yyyy HH:mm`.
+    pub fn parse_date(
+        &self,
+        text: &str,
+        pattern: Option<&str>,
+    ) -> Result<DateTime<Utc>, DataError> {
+        let format = to_chrono_format(pattern.unwrap_or(self.date_pattern));
+        let text = text.trim();
+        NaiveDateTime::parse_from_str(text, &format)
+            .or_else(|_| {
+                NaiveDate::parse_from_str(text, &format)
+                    .map(|date| date.and_hms_opt(0, 0, 0).unwrap_or_default())
+            })
+            .map(|date_time| date_time.and_utc())
+            .map_err(|_| DataError::StringConversionError {
+                value: text.to_string(),
+                type_name: format!("date ({})", pattern.unwrap_or(self.date_pattern)),
+            })
+    }
+
+    pub fn format_date(&self, date_time: &DateTime<Utc>, pattern: Option<&str>) -> String {
+        let format = to_chrono_format(pattern.unwrap_or(self.date_pattern));
+        date_time.format(&format).to_string()
+    }
+
+    fn is_space_grouped(&self) -> bool {
+        is_space(self.grouping_separator)
+    }
+
+    fn format_decimal(&self, decimal: &BigDecimal) -> String {
+        let plain = decimal.to_plain_string();
+        let (sign, unsigned) = match plain.strip_prefix('-') {
+            Some(rest) => ("-", rest),
+            None => ("", plain.as_str()),
+        };
+        let (whole, fraction) = match unsigned.split_once('.') {
+            Some((whole, fraction)) => (whole, Some(fraction)),
+            None => (unsigned, None),
+        };
+        let mut result = String::from(sign);
+        for (index, digit) in whole.chars().enumerate() {
+            if index > 0 && (whole.len() - index) % 3 == 0 {
+                result.push(self.grouping_separator);
+            }
+            result.push(digit);
+        }
+        if let Some(fraction) = fraction {
+            result.push(self.decimal_separator);
+            result.push_str(fraction);
+        }
+        result
+    }
+}
+
+fn is_space(c: char) -> bool {
+    matches!(c, ' ' | '\u{a0}' | '\u{202f}')
+}
+
+// Translates a date pattern such as `dd-MM-yyyy` to a chrono format string such as `%d-%m-%Y`.
+fn to_chrono_format(pattern: &str) -> String {
+    let mut format = String::new();
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        let mut count = 1;
+        while chars.peek() == Some(&c) {
+            chars.next();
+            count += 1;
+        }
+        let specifier = match (c, count) {
+            ('d', 1) => "%-d",
+            ('d', _) => "%d",
+            ('M', 1) => "%-m",
+            ('M', 2) => "%m",
+            ('M', 3) => "%b",
+            ('M', _) => "%B",
+            ('y', 2) => "%y",
+            ('y', _) => "%Y",
+            ('H', 1) => "%-H",
+            ('H', _) => "%H",
+            ('m', 1) => "%-M",
+            ('m', _) => "%M",
+            ('s', 1) => "%-S",
+            ('s', _) => "%S",
+            ('%', _) => {
+                format.push_str(&"%%".repeat(count));
+                continue;
+            }
+            _ => {
+                let _ = write!(format, "{}", c.to_string().repeat(count));
+                continue;
+            }
+        };
+        format.push_str(specifier);
+    }
+    format
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bigdecimal::num_bigint::BigInt;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_parse_number() {
+        let dutch = Locale::from_tag("nl-NL").unwrap();
+        let cases = [
+            ("1.234,56", "1234.56"),
+            ("1234,56", "1234.56"),
+            ("-1.000.000", "-1000000"),
+            ("0,5", "0.5"),
+        ];
+        for (text, expected) in cases {
+            let number = dutch.parse_number(text).unwrap();
+            assert_eq!(
+                number,
+                NumericValue::try_from(expected).unwrap(),
+                "{}",
+                text
+            );
+        }
+        for text in ["1.23,4", "1,2,3", "12.34.567", "", "1,"] {
+            assert!(dutch.parse_number(text).is_err(), "{}", text);
+        }
+        let american = Locale::from_tag("en").unwrap();
+        assert_eq!(
+            american.parse_number("1,234.56").unwrap(),
+            NumericValue::Decimal(BigDecimal::from_str("1234.56").unwrap())
+        );
+        let french = Locale::from_tag("fr_FR").unwrap();
+        assert_eq!(
+            french.parse_number("1 234").unwrap(),
+            NumericValue::Integer(BigInt::from(1234))
+        );
+    }
+
+    #[test]
+    fn test_format_number() {
+        let german = Locale::from_tag("de").unwrap();
+        let number = NumericValue::try_from("-1234567.891").unwrap();
+        assert_eq!(german.format_number(&number, None), "-1.234.567,891");
+        assert_eq!(german.format_number(&number, Some(2)), "-1.234.567,89");
+        assert_eq!(
+            german.format_number(&NumericValue::Integer(BigInt::from(100)), Some(2)),
+            "100,00"
+        );
+        assert!(Locale::from_tag("xx-XX").is_err());
+    }
+
+    #[test]
+    fn test_money() {
+        let dutch = Locale::from_tag("nl-NL").unwrap();
+        let money = dutch.parse_money("€ 1.234,5").unwrap();
+        assert_eq!(dutch.format_money(&money), "€ 1.234,50");
+        let german = Locale::from_tag("de-DE").unwrap();
+        assert_eq!(german.format_money(&money), "1.234,50 €");
+        let american = Locale::from_tag("en-US").unwrap();
+        let money = american.parse_money("-$12.3").unwrap();
+        assert_eq!(american.format_money(&money), "-$12.30");
+        let money = american.parse_money("1,000 CHF").unwrap();
+        assert_eq!(american.format_money(&money), "CHF 1,000.00");
+    }
+
+    #[test]
+    fn test_dates() {
+        let dutch = Locale::from_tag("nl-NL").unwrap();
+        let date = dutch.parse_date("31-12-2024", None).unwrap();
+        assert_eq!(date.to_rfc3339(), "2024-12-31T00:00:00+00:00");
+        assert_eq!(dutch.format_date(&date, None), "31-12-2024");
+        let date_time = dutch
+            .parse_date("1.2.2024 13:05", Some("d.M.yyyy HH:mm"))
+            .unwrap();
+        assert_eq!(
+            dutch.format_date(&date_time, Some("yyyy-MM-dd HH:mm")),
+            "2024-02-01 13:05"
+        );
+        assert!(dutch.parse_date("2024-12-31", None).is_err());
+    }
+}
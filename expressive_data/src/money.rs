@@ -0,0 +1,350 @@
+use crate::error::DataError;
+use crate::numeric::NumericValue;
+use bigdecimal::{BigDecimal, RoundingMode, Zero};
+use num_bigint::{BigInt, Sign};
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use std::ops::{Add, Sub};
+
+// ISO-4217 alphabetic codes with the number of digits of their minor unit.
+const CURRENCIES: &[(&str, i64)] = &[
+    ("AED", 2),
+    ("ARS", 2),
+    ("AUD", 2),
+    ("BGN", 2),
+    ("BHD", 3),
+    ("BRL", 2),
+    ("CAD", 2),
+    ("CHF", 2),
+    ("CLP", 0),
+    ("CNY", 2),
+    ("COP", 2),
+    ("CZK", 2),
+    ("DKK", 2),
+    ("EGP", 2),
+    ("EUR", 2),
+    ("GBP", 2),
+    ("HKD", 2),
+    ("HUF", 2),
+    ("IDR", 2),
+    ("ILS", 2),
+    ("INR", 2),
+    ("IQD", 3),
+    ("ISK", 0),
+    ("JOD", 3),
+    ("JPY", 0),
+    ("KRW", 0),
+    ("KWD", 3),
+    ("LYD", 3),
+    ("MAD", 2),
+    ("MXN", 2),
+    ("MYR", 2),
+    ("NGN", 2),
+    ("NOK", 2),
+    ("NZD", 2),
+    ("OMR", 3),
+    ("PHP", 2),
+    ("PKR", 2),
+    ("PLN", 2),
+    ("QAR", 2),
+    ("RON", 2),
+    ("RSD", 2),
+    ("SAR", 2),
+    ("SEK", 2),
+    ("SGD", 2),
+    ("THB", 2),
+    ("TND", 3),
+    ("TRY", 2),
+    ("TWD", 2),
+    ("UAH", 2),
+    ("UGX", 0),
+    ("USD", 2),
+    ("VND", 0),
+    ("XAF", 0),
+    ("XOF", 0),
+    ("ZAR", 2),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Currency {
+    code: &'static str,
+    minor_units: i64,
+}
+
+impl Currency {
+    pub fn from_code(code: &str) -> Result<Currency, DataError> {
+        CURRENCIES
+            .iter()
+            .find(|(known_code, _)| *known_code == code)
+            .map(|(code, minor_units)| Currency {
+                code,
+                minor_units: *minor_units,
+            })
+            .ok_or(DataError::UnknownCurrency {
+                code: code.to_string(),
+            })
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.code
+    }
+
+    /// The number of decimal places amounts in this currency are kept at.
+    pub fn minor_units(&self) -> i64 {
+        self.minor_units
+    }
+}
+
+impl Display for Currency {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.code)
+    }
+}
+
+// Currencies serialize as their ISO-4217 code.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Currency {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.code)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Currency {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let code = <String as serde::Deserialize>::deserialize(deserializer)?;
+        Currency::from_code(&code).map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Money {
+    amount: BigDecimal,
+    currency: Currency,
+}
+
+impl Money {
+    /// Creates an amount of money, rounding half-even to the minor unit of the currency.
+    pub fn new(amount: BigDecimal, currency: Currency) -> Money {
+        let amount = amount.with_scale_round(currency.minor_units, RoundingMode::HalfEven);
+        Money { amount, currency }
+    }
+
+    pub fn amount(&self) -> &BigDecimal {
+        &self.amount
+    }
+
+    pub fn currency(&self) -> Currency {
+        self.currency
+    }
+
+    pub fn into_parts(self) -> (BigDecimal, Currency) {
+        (self.amount, self.currency)
+    }
+
+    pub fn multiply(self, factor: NumericValue) -> Money {
+        Money::new(self.amount * to_big_decimal(factor), self.currency)
+    }
+
+    pub fn divide(self, divisor: NumericValue) -> Result<Money, DataError> {
+        let divisor = to_big_decimal(divisor);
+        if divisor.is_zero() {
+            return Err(DataError::DivisionByZero);
+        }
+        Ok(Money::new(self.amount / divisor, self.currency))
+    }
+
+    /// Divides two amounts in the same currency into a plain ratio.
+    pub fn ratio(self, other: Money) -> Result<NumericValue, DataError> {
+        self.check_currency(&other)?;
+        if other.amount.is_zero() {
+            return Err(DataError::DivisionByZero);
+        }
+        Ok(NumericValue::Decimal(self.amount / other.amount))
+    }
+
+    /// Splits the amount into parts proportional to the given ratios without losing any minor
+    /// units. Rounding leftovers are handed out one minor unit at a time starting at the first
+    /// part, skipping the parts with a zero ratio.
+    pub fn allocate(&self, ratios: &[NumericValue]) -> Result<Vec<Money>, DataError> {
+        if ratios.is_empty() {
+            return Err(DataError::AllocationError {
+                message: "at least one ratio is required".to_string(),
+            });
+        }
+        let ratios: Vec<BigDecimal> = ratios.iter().cloned().map(to_big_decimal).collect();
+        if ratios.iter().any(|ratio| ratio.sign() == Sign::Minus) {
+            return Err(DataError::AllocationError {
+                message: "ratios may not be negative".to_string(),
+            });
+        }
+        let total_ratio: BigDecimal = ratios.iter().sum();
+        if total_ratio.is_zero() {
+            return Err(DataError::AllocationError {
+                message: "the ratios must not all be zero".to_string(),
+            });
+        }
+
+        let (total_units, _) = self
+            .amount
+            .with_scale(self.currency.minor_units)
+            .into_bigint_and_exponent();
+        let mut units: Vec<BigInt> = ratios
+            .iter()
+            .map(|ratio| {
+                let share = BigDecimal::from(total_units.clone()) * ratio / &total_ratio;
+                share
+                    .with_scale_round(0, RoundingMode::Down)
+                    .into_bigint_and_exponent()
+                    .0
+            })
+            .collect();
+
+        let allocated: BigInt = units.iter().sum();
+        let mut remainder = total_units - allocated;
+        let step = match remainder.sign() {
+            Sign::Minus => BigInt::from(-1),
+            _ => BigInt::from(1),
+        };
+        // Rounding down leaves less than one minor unit per part with a ratio above zero, so the
+        // parts with a zero ratio never need to take a leftover.
+        for (part, ratio) in units.iter_mut().zip(&ratios) {
+            if remainder.is_zero() {
+                break;
+            }
+            if ratio.is_zero() {
+                continue;
+            }
+            *part += &step;
+            remainder -= &step;
+        }
+
+        Ok(units
+            .into_iter()
+            .map(|part| {
+                Money::new(
+                    BigDecimal::from_bigint(part, self.currency.minor_units),
+                    self.currency,
+                )
+            })
+            .collect())
+    }
+
+    pub fn compare(&self, other: &Money) -> Result<Ordering, DataError> {
+        self.check_currency(other)?;
+        Ok(self.amount.cmp(&other.amount))
+    }
+
+    fn check_currency(&self, other: &Money) -> Result<(), DataError> {
+        if self.currency == other.currency {
+            Ok(())
+        } else {
+            Err(DataError::CurrencyMismatch {
+                left: self.currency.to_string(),
+                right: other.currency.to_string(),
+            })
+        }
+    }
+}
+
+impl Add for Money {
+    type Output = Result<Money, DataError>;
+
+    fn add(self, other: Money) -> Self::Output {
+        self.check_currency(&other)?;
+        Ok(Money::new(self.amount + other.amount, self.currency))
+    }
+}
+
+impl Sub for Money {
+    type Output = Result<Money, DataError>;
+
+    fn sub(self, other: Money) -> Self::Output {
+        self.check_currency(&other)?;
+        Ok(Money::new(self.amount - other.amount, self.currency))
+    }
+}
+
+impl Display for Money {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.currency, self.amount)
+    }
+}
+
+fn to_big_decimal(number: NumericValue) -> BigDecimal {
+    match number {
+        NumericValue::Decimal(decimal) => decimal,
+        NumericValue::Integer(integer) => BigDecimal::from(integer),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn euros(amount: &str) -> Money {
+        Money::new(
+            BigDecimal::from_str(amount).unwrap(),
+            Currency::from_code("EUR").unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_amount_is_kept_at_minor_unit_scale() {
+        let money = euros("12.5");
+        assert_eq!(money.to_string(), "EUR 12.50");
+        let yen = Money::new(
+            BigDecimal::from_str("100.5").unwrap(),
+            Currency::from_code("JPY").unwrap(),
+        );
+        assert_eq!(yen.to_string(), "JPY 100");
+    }
+
+    #[test]
+    fn test_unknown_currency() {
+        assert!(Currency::from_code("XYZ").is_err());
+    }
+
+    #[test]
+    fn test_mixing_currencies_is_refused() {
+        let dollars = Money::new(BigDecimal::from(1), Currency::from_code("USD").unwrap());
+        let result = euros("1.00") + dollars;
+        assert!(matches!(result, Err(DataError::CurrencyMismatch { .. })));
+    }
+
+    #[test]
+    fn test_allocate_does_not_lose_cents() {
+        let parts = euros("100.00")
+            .allocate(&[
+                NumericValue::Integer(BigInt::from(1)),
+                NumericValue::Integer(BigInt::from(1)),
+                NumericValue::Integer(BigInt::from(1)),
+            ])
+            .unwrap();
+        assert_eq!(parts, vec![euros("33.34"), euros("33.33"), euros("33.33")]);
+    }
+
+    #[test]
+    fn test_allocate_negative_amount() {
+        let parts = euros("-0.05")
+            .allocate(&[
+                NumericValue::Integer(BigInt::from(3)),
+                NumericValue::Integer(BigInt::from(7)),
+            ])
+            .unwrap();
+        assert_eq!(parts, vec![euros("-0.02"), euros("-0.03")]);
+    }
+
+    #[test]
+    fn test_allocate_skips_zero_ratios() {
+        let parts = euros("0.01")
+            .allocate(&[
+                NumericValue::Integer(BigInt::from(0)),
+                NumericValue::Integer(BigInt::from(1)),
+                NumericValue::Integer(BigInt::from(1)),
+            ])
+            .unwrap();
+        assert_eq!(parts, vec![euros("0.00"), euros("0.01"), euros("0.00")]);
+    }
+}
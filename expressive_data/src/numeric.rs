@@ -1,11 +1,27 @@
 use crate::error::DataError;
-use bigdecimal::{BigDecimal, Num};
+use bigdecimal::{BigDecimal, Num, Zero};
 use num_bigint::BigInt;
+use std::cmp::Ordering;
 use std::fmt::{Display, Formatter};
 use std::ops::{Add, Div, Mul, Sub};
 
 const DEFAULT_DECIMAL_SCALE: i64 = 10;
 
+/// The largest magnitude of an exponent, which keeps a single power from computing a number, or a
+/// unit factor, of unbounded size.
+pub const MAX_EXPONENT: u32 = 1_000;
+
+/// The magnitude of an exponent, or an error when it exceeds [`MAX_EXPONENT`].
+pub(crate) fn exponent_magnitude(exponent: i64) -> Result<u32, DataError> {
+    u32::try_from(exponent.unsigned_abs())
+        .ok()
+        .filter(|magnitude| *magnitude <= MAX_EXPONENT)
+        .ok_or_else(|| DataError::ExponentTooLarge {
+            exponent: exponent.to_string(),
+            max: MAX_EXPONENT,
+        })
+}
+
 pub trait Numeric<N>
 where
     N: Clone + Add + Sub + Mul + Div + PartialEq + PartialOrd,
@@ -18,9 +34,66 @@ where
 }
 
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum NumericValue {
-    Decimal(BigDecimal),
-    Integer(BigInt),
+    Decimal(
+        #[cfg_attr(feature = "serde", serde(with = "crate::serialization::as_string"))] BigDecimal,
+    ),
+    Integer(#[cfg_attr(feature = "serde", serde(with = "crate::serialization::as_string"))] BigInt),
+}
+
+impl NumericValue {
+    pub fn is_zero(&self) -> bool {
+        match self {
+            NumericValue::Decimal(d) => d.is_zero(),
+            NumericValue::Integer(i) => i.is_zero(),
+        }
+    }
+
+    /// Compares two numbers by value, regardless of whether they are integers or decimals.
+    pub fn compare(&self, other: &NumericValue) -> Ordering {
+        match (self, other) {
+            (NumericValue::Integer(left), NumericValue::Integer(right)) => left.cmp(right),
+            (left, right) => left.to_big_decimal().cmp(&right.to_big_decimal()),
+        }
+    }
+
+    /// Raises the number to an integer power, which is exact for non-negative exponents. The
+    /// exponent may be at most [`MAX_EXPONENT`] in magnitude.
+    pub fn power(&self, exponent: i32) -> Result<NumericValue, DataError> {
+        let magnitude = exponent_magnitude(i64::from(exponent))?;
+        if exponent < 0 && self.is_zero() {
+            return Err(DataError::DivisionByZero);
+        }
+        // Exponentiation by squaring.
+        let result = match self {
+            NumericValue::Integer(base) => NumericValue::Integer(base.pow(magnitude)),
+            NumericValue::Decimal(base) => {
+                let (mut result, mut base, mut remaining) =
+                    (BigDecimal::from(1), base.clone(), magnitude);
+                while remaining > 0 {
+                    if remaining & 1 == 1 {
+                        result = &result * &base;
+                    }
+                    base = &base * &base;
+                    remaining >>= 1;
+                }
+                NumericValue::Decimal(result)
+            }
+        };
+        match exponent < 0 {
+            true => Ok(NumericValue::Integer(BigInt::from(1)) / result),
+            false => Ok(result),
+        }
+    }
+
+    fn to_big_decimal(&self) -> BigDecimal {
+        match self {
+            NumericValue::Decimal(d) => d.clone(),
+            NumericValue::Integer(i) => BigDecimal::from(i.clone()),
+        }
+    }
 }
 
 impl TryFrom<String> for NumericValue {
@@ -157,7 +230,7 @@ impl Div for NumericValue {
     fn div(self, other: NumericValue) -> NumericValue {
         match (self, other) {
             (NumericValue::Decimal(left_number), NumericValue::Decimal(right_number)) => {
-                NumericValue::Decimal(left_number - right_number)
+                NumericValue::Decimal(left_number / right_number)
             }
             (NumericValue::Integer(left_number), NumericValue::Integer(right_number)) => {
                 NumericValue::Decimal(
@@ -166,10 +239,10 @@ impl Div for NumericValue {
                 )
             }
             (NumericValue::Decimal(left_number), NumericValue::Integer(right_number)) => {
-                NumericValue::Decimal(left_number - right_number)
+                NumericValue::Decimal(left_number / BigDecimal::from(right_number))
             }
             (NumericValue::Integer(left_number), NumericValue::Decimal(right_number)) => {
-                NumericValue::Decimal(left_number - right_number)
+                NumericValue::Decimal(BigDecimal::from(left_number) / right_number)
             }
         }
     }
@@ -0,0 +1,648 @@
+use crate::error::DataError;
+use crate::numeric::{exponent_magnitude, NumericValue};
+use bigdecimal::{BigDecimal, One, Zero};
+use num_bigint::BigInt;
+use num_integer::Integer;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::ops::{Add, Div, Mul, Sub};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+// Symbol, factor relative to the coherent SI unit and dimension of the standard units.
+const STANDARD_UNITS: &[(&str, &str, Dimension)] = &[
+    ("m", "1", Dimension::LENGTH),
+    ("km", "1000", Dimension::LENGTH),
+    ("cm", "0.01", Dimension::LENGTH),
+    ("mm", "0.001", Dimension::LENGTH),
+    ("inch", "0.0254", Dimension::LENGTH),
+    ("ft", "0.3048", Dimension::LENGTH),
+    ("yd", "0.9144", Dimension::LENGTH),
+    ("mi", "1609.344", Dimension::LENGTH),
+    ("nmi", "1852", Dimension::LENGTH),
+    ("kg", "1", Dimension::MASS),
+    ("g", "0.001", Dimension::MASS),
+    ("mg", "0.000001", Dimension::MASS),
+    ("t", "1000", Dimension::MASS),
+    ("lb", "0.45359237", Dimension::MASS),
+    ("oz", "0.028349523125", Dimension::MASS),
+    ("s", "1", Dimension::TIME),
+    ("ms", "0.001", Dimension::TIME),
+    ("min", "60", Dimension::TIME),
+    ("h", "3600", Dimension::TIME),
+    ("d", "86400", Dimension::TIME),
+    ("wk", "604800", Dimension::TIME),
+    ("l", "0.001", Dimension::VOLUME),
+    ("dl", "0.0001", Dimension::VOLUME),
+    ("cl", "0.00001", Dimension::VOLUME),
+    ("ml", "0.000001", Dimension::VOLUME),
+    ("gal", "0.003785411784", Dimension::VOLUME),
+];
+
+/// The exponents of the base dimensions length, mass and time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Dimension {
+    pub length: i32,
+    pub mass: i32,
+    pub time: i32,
+}
+
+impl Dimension {
+    pub const NONE: Dimension = Dimension::new(0, 0, 0);
+    pub const LENGTH: Dimension = Dimension::new(1, 0, 0);
+    pub const MASS: Dimension = Dimension::new(0, 1, 0);
+    pub const TIME: Dimension = Dimension::new(0, 0, 1);
+    pub const VOLUME: Dimension = Dimension::new(3, 0, 0);
+
+    pub const fn new(length: i32, mass: i32, time: i32) -> Dimension {
+        Dimension { length, mass, time }
+    }
+
+    fn combine(self, other: Dimension, sign: i32) -> Dimension {
+        Dimension::new(
+            self.length + sign * other.length,
+            self.mass + sign * other.mass,
+            self.time + sign * other.time,
+        )
+    }
+
+    fn power(self, exponent: i32) -> Dimension {
+        Dimension::new(
+            self.length * exponent,
+            self.mass * exponent,
+            self.time * exponent,
+        )
+    }
+}
+
+// An exact fraction kept in lowest terms, so that compound units such as `km/h` convert exactly.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Ratio {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialization::as_string"))]
+    numerator: BigInt,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialization::as_string"))]
+    denominator: BigInt,
+}
+
+impl Ratio {
+    fn new(numerator: BigInt, denominator: BigInt) -> Ratio {
+        let divisor = numerator.gcd(&denominator);
+        Ratio {
+            numerator: numerator / &divisor,
+            denominator: denominator / divisor,
+        }
+    }
+
+    fn one() -> Ratio {
+        Ratio::new(BigInt::one(), BigInt::one())
+    }
+
+    fn from_decimal(decimal: &BigDecimal) -> Ratio {
+        let (digits, scale) = decimal.as_bigint_and_exponent();
+        match scale >= 0 {
+            true => Ratio::new(digits, BigInt::from(10u32).pow(scale as u32)),
+            false => Ratio::new(
+                digits * BigInt::from(10u32).pow(-scale as u32),
+                BigInt::one(),
+            ),
+        }
+    }
+
+    fn multiply(&self, other: &Ratio) -> Ratio {
+        Ratio::new(
+            &self.numerator * &other.numerator,
+            &self.denominator * &other.denominator,
+        )
+    }
+
+    fn divide(&self, other: &Ratio) -> Ratio {
+        Ratio::new(
+            &self.numerator * &other.denominator,
+            &self.denominator * &other.numerator,
+        )
+    }
+
+    fn power(&self, exponent: u32) -> Ratio {
+        Ratio::new(self.numerator.pow(exponent), self.denominator.pow(exponent))
+    }
+}
+
+// Units serialize with their factor and dimension, so that units from any registry deserialize.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Unit {
+    symbol: String,
+    factor: Ratio,
+    dimension: Dimension,
+}
+
+impl Unit {
+    /// A unit whose magnitudes are converted to the coherent SI unit by multiplying with the
+    /// factor, which must be positive.
+    pub fn new(symbol: &str, factor: BigDecimal, dimension: Dimension) -> Result<Unit, DataError> {
+        if factor <= BigDecimal::zero() {
+            return Err(DataError::InvalidUnitFactor {
+                symbol: symbol.to_string(),
+                factor: factor.to_string(),
+            });
+        }
+        Ok(Unit {
+            symbol: symbol.to_string(),
+            factor: Ratio::from_decimal(&factor),
+            dimension,
+        })
+    }
+
+    /// The coherent SI unit of a dimension, such as `kg*m/s^2` for force.
+    pub fn base(dimension: Dimension) -> Unit {
+        let factors = [
+            ("kg", dimension.mass),
+            ("m", dimension.length),
+            ("s", dimension.time),
+        ];
+        let format = |exponent: i32, symbol: &str| match exponent {
+            1 => symbol.to_string(),
+            _ => format!("{}^{}", symbol, exponent),
+        };
+        let numerator: Vec<String> = factors
+            .iter()
+            .filter(|(_, exponent)| *exponent > 0)
+            .map(|(symbol, exponent)| format(*exponent, symbol))
+            .collect();
+        let denominator: Vec<String> = factors
+            .iter()
+            .filter(|(_, exponent)| *exponent < 0)
+            .map(|(symbol, exponent)| format(-exponent, symbol))
+            .collect();
+        let mut symbol = match numerator.is_empty() {
+            true => "1".to_string(),
+            false => numerator.join("*"),
+        };
+        for factor in denominator {
+            symbol = format!("{}/{}", symbol, factor);
+        }
+        Unit {
+            symbol,
+            factor: Ratio::one(),
+            dimension,
+        }
+    }
+
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    /// The factor that converts a magnitude in this unit to the coherent SI unit, as an exact
+    /// numerator and denominator.
+    pub fn factor(&self) -> (&BigInt, &BigInt) {
+        (&self.factor.numerator, &self.factor.denominator)
+    }
+
+    pub fn dimension(&self) -> Dimension {
+        self.dimension
+    }
+
+    pub fn is_dimensionless(&self) -> bool {
+        self.dimension == Dimension::NONE
+    }
+
+    fn multiply(&self, other: &Unit) -> Unit {
+        if self.is_one() {
+            return other.clone();
+        } else if other.is_one() {
+            return self.clone();
+        }
+        Unit {
+            symbol: format!("{}*{}", self.symbol, other.symbol),
+            factor: self.factor.multiply(&other.factor),
+            dimension: self.dimension.combine(other.dimension, 1),
+        }
+    }
+
+    fn divide(&self, other: &Unit) -> Unit {
+        if other.is_one() {
+            return self.clone();
+        } else if self.is_one() {
+            return other.inverse();
+        }
+        Unit {
+            symbol: format!("{}/{}", self.symbol, other.symbol),
+            factor: self.factor.divide(&other.factor),
+            dimension: self.dimension.combine(other.dimension, -1),
+        }
+    }
+
+    // The unit plain numbers are given when they take part in unit arithmetic.
+    fn is_one(&self) -> bool {
+        self.is_dimensionless() && self.factor == Ratio::one()
+    }
+
+    fn power(&self, exponent: i32) -> Result<Unit, DataError> {
+        let factor = self.factor.power(exponent_magnitude(i64::from(exponent))?);
+        Ok(Unit {
+            symbol: format!("{}^{}", self.symbol, exponent),
+            factor: match exponent < 0 {
+                true => Ratio::one().divide(&factor),
+                false => factor,
+            },
+            dimension: self.dimension.power(exponent),
+        })
+    }
+
+    fn inverse(&self) -> Unit {
+        Unit {
+            symbol: format!("1/{}", self.symbol),
+            factor: Ratio::one().divide(&self.factor),
+            dimension: self.dimension.power(-1),
+        }
+    }
+
+    /// The exact ratio between a magnitude in this unit and the same magnitude in another one.
+    fn ratio(&self, other: &Unit) -> Ratio {
+        self.factor.divide(&other.factor)
+    }
+}
+
+impl Display for Unit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.symbol)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct UnitRegistry {
+    units: HashMap<String, Unit>,
+}
+
+impl UnitRegistry {
+    pub fn new() -> UnitRegistry {
+        UnitRegistry::default()
+    }
+
+    /// The registry with the metric, imperial, time and volume units available to expressions.
+    pub fn standard() -> &'static UnitRegistry {
+        static STANDARD: OnceLock<UnitRegistry> = OnceLock::new();
+        STANDARD.get_or_init(|| {
+            let mut registry = UnitRegistry::new();
+            for (symbol, factor, dimension) in STANDARD_UNITS {
+                let factor = BigDecimal::from_str(factor).expect("a valid unit factor");
+                let unit = Unit::new(symbol, factor, *dimension).expect("a positive unit factor");
+                registry.register(unit);
+            }
+            registry
+        })
+    }
+
+    pub fn register(&mut self, unit: Unit) {
+        self.units.insert(unit.symbol.clone(), unit);
+    }
+
+    pub fn get(&self, symbol: &str) -> Option<&Unit> {
+        self.units.get(symbol)
+    }
+
+    /// Parses a unit expression such as `kg`, `m/s` or `kg*m/s^2` from registered symbols.
+    pub fn parse(&self, text: &str) -> Result<Unit, DataError> {
+        let mut unit: Option<Unit> = None;
+        let mut divide = false;
+        let mut rest = text;
+        loop {
+            let end = rest.find(['*', '/']).unwrap_or(rest.len());
+            let factor = self.parse_factor(&rest[..end], text)?;
+            unit = Some(match (unit, divide) {
+                (None, false) => factor,
+                (None, true) => factor.inverse(),
+                (Some(unit), false) => unit.multiply(&factor),
+                (Some(unit), true) => unit.divide(&factor),
+            });
+            if end == rest.len() {
+                break;
+            }
+            divide = rest[end..].starts_with('/');
+            rest = &rest[end + 1..];
+        }
+        let mut unit = unit.ok_or(DataError::UnknownUnit {
+            symbol: text.to_string(),
+        })?;
+        unit.symbol = text.to_string();
+        Ok(unit)
+    }
+
+    fn parse_factor(&self, factor: &str, text: &str) -> Result<Unit, DataError> {
+        let unknown = || DataError::UnknownUnit {
+            symbol: text.to_string(),
+        };
+        let (symbol, exponent) = match factor.split_once('^') {
+            Some((symbol, exponent)) => (symbol, exponent.parse::<i32>().map_err(|_| unknown())?),
+            None => (factor, 1),
+        };
+        let unit = self.get(symbol).ok_or_else(unknown)?;
+        match exponent {
+            1 => Ok(unit.clone()),
+            _ => unit.power(exponent),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quantity {
+    magnitude: NumericValue,
+    unit: Unit,
+}
+
+impl Quantity {
+    pub fn new(magnitude: NumericValue, unit: Unit) -> Quantity {
+        Quantity { magnitude, unit }
+    }
+
+    pub fn magnitude(&self) -> &NumericValue {
+        &self.magnitude
+    }
+
+    pub fn unit(&self) -> &Unit {
+        &self.unit
+    }
+
+    pub fn into_parts(self) -> (NumericValue, Unit) {
+        (self.magnitude, self.unit)
+    }
+
+    /// Converts the quantity to another unit of the same dimension. The conversion must be exact,
+    /// use `convert_rounded` for conversions that do not have a finite decimal expansion.
+    pub fn convert(self, unit: &Unit) -> Result<Quantity, DataError> {
+        self.check_dimension(unit)?;
+        let ratio = self.unit.ratio(unit);
+        let magnitude =
+            scale_exactly(self.magnitude, &ratio).ok_or(DataError::InexactConversion {
+                from: self.unit.symbol.clone(),
+                to: unit.symbol.clone(),
+            })?;
+        Ok(Quantity::new(magnitude, unit.clone()))
+    }
+
+    /// Converts the quantity to another unit of the same dimension, rounding half-even to the
+    /// given number of decimal places.
+    pub fn convert_rounded(self, unit: &Unit, scale: i64) -> Result<Quantity, DataError> {
+        self.check_dimension(unit)?;
+        let ratio = self.unit.ratio(unit);
+        let magnitude = to_big_decimal(self.magnitude) * BigDecimal::from(ratio.numerator)
+            / BigDecimal::from(ratio.denominator);
+        let magnitude = magnitude.with_scale_round(scale, bigdecimal::RoundingMode::HalfEven);
+        Ok(Quantity::new(
+            NumericValue::Decimal(magnitude),
+            unit.clone(),
+        ))
+    }
+
+    pub fn scale(self, factor: NumericValue) -> Quantity {
+        Quantity::new(self.magnitude * factor, self.unit)
+    }
+
+    pub fn power(self, exponent: i32) -> Result<Quantity, DataError> {
+        Ok(Quantity::new(
+            self.magnitude.power(exponent)?,
+            self.unit.power(exponent)?,
+        ))
+    }
+
+    pub fn divide_by(self, divisor: NumericValue) -> Result<Quantity, DataError> {
+        if divisor.is_zero() {
+            return Err(DataError::DivisionByZero);
+        }
+        Ok(Quantity::new(self.magnitude / divisor, self.unit))
+    }
+
+    pub fn divide_into(self, dividend: NumericValue) -> Result<Quantity, DataError> {
+        if self.magnitude.is_zero() {
+            return Err(DataError::DivisionByZero);
+        }
+        Ok(Quantity::new(
+            dividend / self.magnitude,
+            self.unit.inverse(),
+        ))
+    }
+
+    /// Picks the unit two quantities of the same dimension are combined in: the left unit when
+    /// the right one converts to it exactly, else the right unit, else the coherent SI unit.
+    fn common_unit(&self, other: &Quantity) -> Result<Unit, DataError> {
+        self.check_dimension(&other.unit)?;
+        let exact = |from: &Unit, to: &Unit| {
+            terminating_power_of_ten(&from.ratio(to).denominator).is_some()
+        };
+        if exact(&other.unit, &self.unit) {
+            Ok(self.unit.clone())
+        } else if exact(&self.unit, &other.unit) {
+            Ok(other.unit.clone())
+        } else {
+            Ok(Unit::base(self.unit.dimension))
+        }
+    }
+
+    /// Compares two quantities of the same dimension exactly, whatever units they are in.
+    pub fn compare(&self, other: &Quantity) -> Result<Ordering, DataError> {
+        self.check_dimension(&other.unit)?;
+        let left = Ratio::from_decimal(&to_big_decimal(self.magnitude.clone()))
+            .multiply(&self.unit.factor);
+        let right = Ratio::from_decimal(&to_big_decimal(other.magnitude.clone()))
+            .multiply(&other.unit.factor);
+        Ok((left.numerator * right.denominator).cmp(&(right.numerator * left.denominator)))
+    }
+
+    fn check_dimension(&self, unit: &Unit) -> Result<(), DataError> {
+        if self.unit.dimension == unit.dimension {
+            Ok(())
+        } else {
+            Err(DataError::DimensionMismatch {
+                left: self.unit.symbol.clone(),
+                right: unit.symbol.clone(),
+            })
+        }
+    }
+
+    /// The plain number a quantity amounts to once its units cancel out, such as `km/m`.
+    pub fn dimensionless_magnitude(&self) -> Option<NumericValue> {
+        if !self.unit.is_dimensionless() {
+            return None;
+        }
+        let ratio = self.unit.ratio(&Unit::base(Dimension::NONE));
+        match scale_exactly(self.magnitude.clone(), &ratio) {
+            Some(magnitude) => Some(magnitude),
+            None => Some(
+                self.magnitude.clone() * NumericValue::Integer(ratio.numerator)
+                    / NumericValue::Integer(ratio.denominator),
+            ),
+        }
+    }
+}
+
+impl Add for Quantity {
+    type Output = Result<Quantity, DataError>;
+
+    fn add(self, other: Quantity) -> Self::Output {
+        let unit = self.common_unit(&other)?;
+        let left = self.convert(&unit)?;
+        let right = other.convert(&unit)?;
+        Ok(Quantity::new(left.magnitude + right.magnitude, unit))
+    }
+}
+
+impl Sub for Quantity {
+    type Output = Result<Quantity, DataError>;
+
+    fn sub(self, other: Quantity) -> Self::Output {
+        let unit = self.common_unit(&other)?;
+        let left = self.convert(&unit)?;
+        let right = other.convert(&unit)?;
+        Ok(Quantity::new(left.magnitude - right.magnitude, unit))
+    }
+}
+
+impl Mul for Quantity {
+    type Output = Quantity;
+
+    fn mul(self, other: Quantity) -> Self::Output {
+        Quantity::new(
+            self.magnitude * other.magnitude,
+            self.unit.multiply(&other.unit),
+        )
+    }
+}
+
+impl Div for Quantity {
+    type Output = Result<Quantity, DataError>;
+
+    fn div(self, other: Quantity) -> Self::Output {
+        if other.magnitude.is_zero() {
+            return Err(DataError::DivisionByZero);
+        }
+        Ok(Quantity::new(
+            self.magnitude / other.magnitude,
+            self.unit.divide(&other.unit),
+        ))
+    }
+}
+
+impl Display for Quantity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.magnitude, self.unit)
+    }
+}
+
+// Returns the power of ten that turns `1 / denominator` into an integer, if there is one.
+fn terminating_power_of_ten(denominator: &BigInt) -> Option<u32> {
+    let mut rest = denominator.clone();
+    let mut twos = 0;
+    let mut fives = 0;
+    while rest.is_even() && !rest.is_zero() {
+        rest /= 2;
+        twos += 1;
+    }
+    while (&rest % 5u32).is_zero() && !rest.is_zero() {
+        rest /= 5;
+        fives += 1;
+    }
+    match rest == BigInt::one() {
+        true => Some(u32::max(twos, fives)),
+        false => None,
+    }
+}
+
+fn scale_exactly(magnitude: NumericValue, ratio: &Ratio) -> Option<NumericValue> {
+    let is_integer = matches!(magnitude, NumericValue::Integer(_));
+    let exact = Ratio::from_decimal(&to_big_decimal(magnitude)).multiply(ratio);
+    if is_integer && exact.denominator.is_one() {
+        return Some(NumericValue::Integer(exact.numerator));
+    }
+    let power = terminating_power_of_ten(&exact.denominator)?;
+    let digits = exact.numerator * (BigInt::from(10u32).pow(power) / exact.denominator);
+    Some(NumericValue::Decimal(BigDecimal::new(digits, power as i64)))
+}
+
+fn to_big_decimal(number: NumericValue) -> BigDecimal {
+    match number {
+        NumericValue::Decimal(decimal) => decimal,
+        NumericValue::Integer(integer) => BigDecimal::from(integer),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quantity(magnitude: &str, unit: &str) -> Quantity {
+        Quantity::new(
+            NumericValue::try_from(magnitude).unwrap(),
+            UnitRegistry::standard().parse(unit).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_parse_compound_unit() {
+        let unit = UnitRegistry::standard().parse("km/h").unwrap();
+        assert_eq!(unit.dimension(), Dimension::new(1, 0, -1));
+        assert_eq!(unit.symbol(), "km/h");
+        assert!(UnitRegistry::standard().parse("furlong").is_err());
+    }
+
+    #[test]
+    fn test_add_compatible_units() {
+        let sum = (quantity("1", "km") + quantity("250", "m")).unwrap();
+        assert_eq!(sum.to_string(), "1.25 km");
+        let sum = (quantity("1", "h") + quantity("30", "min")).unwrap();
+        assert_eq!(sum.to_string(), "90 min");
+    }
+
+    #[test]
+    fn test_add_falls_back_to_si_unit() {
+        let sum = (quantity("1", "kg") + quantity("1", "lb")).unwrap();
+        assert_eq!(sum.unit().symbol(), "kg");
+        assert_eq!(
+            sum.magnitude(),
+            &NumericValue::try_from("1.45359237").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_unit_factor_must_be_positive() {
+        for factor in ["0", "-0.5"] {
+            let factor = BigDecimal::from_str(factor).unwrap();
+            let result = Unit::new("bad", factor, Dimension::LENGTH);
+            assert!(matches!(result, Err(DataError::InvalidUnitFactor { .. })));
+        }
+    }
+
+    #[test]
+    fn test_dimension_mismatch() {
+        let result = quantity("1", "kg") + quantity("1", "m");
+        assert!(matches!(result, Err(DataError::DimensionMismatch { .. })));
+    }
+
+    #[test]
+    fn test_exact_conversion() {
+        let unit = UnitRegistry::standard().parse("g").unwrap();
+        let converted = quantity("2.5", "lb").convert(&unit).unwrap();
+        assert_eq!(
+            converted.magnitude(),
+            &NumericValue::try_from("1133.980925").unwrap()
+        );
+        let unit = UnitRegistry::standard().parse("lb").unwrap();
+        let result = quantity("1", "kg").convert(&unit);
+        assert!(matches!(result, Err(DataError::InexactConversion { .. })));
+    }
+
+    #[test]
+    fn test_units_cancel_out() {
+        let ratio = (quantity("3", "km") / quantity("500", "m")).unwrap();
+        assert_eq!(
+            ratio.dimensionless_magnitude(),
+            Some(NumericValue::Decimal(BigDecimal::from(6)))
+        );
+        let speed = quantity("36", "km") / quantity("1", "h");
+        let unit = UnitRegistry::standard().parse("m/s").unwrap();
+        let converted = speed.unwrap().convert(&unit).unwrap();
+        assert_eq!(converted.to_string(), "10 m/s");
+    }
+}
@@ -1,17 +1,223 @@
+use crate::error::DataError;
+use crate::money::{Currency, Money};
+use crate::numeric::{NumericValue, MAX_EXPONENT};
+use crate::unit::{Dimension, Quantity, Unit};
 use bigdecimal::num_bigint::BigInt;
-use bigdecimal::BigDecimal;
+use bigdecimal::{BigDecimal, Zero};
 use chrono::{DateTime, Utc};
+use num_integer::Integer;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
 use std::fmt::{Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::ops::{Add, Div, Mul, Not, Sub};
 
+/// A value of an expression. With the `serde` feature values serialize tagged with their type, e.g.
+/// `{"decimal": "12.50"}`, where decimals and integers are strings so that they keep their
+/// precision and date times are RFC 3339 strings.
+///
+/// Values are equal when they represent the same value, so `2` equals `2.00`, `EUR 5` equals `EUR
+/// 5.00` and `1 km` equals `1000 m`. Values of different kinds are never equal and are ordered by
+/// kind: null, booleans, numbers, money, quantities, strings, date times, lists and maps. Within a
+/// kind money is ordered by currency and then amount, quantities by dimension and then magnitude in
+/// base units, and lists and maps element by element. Hashing is consistent with equality.
+///
+/// This total order is meant for sorting, grouping and map keys, comparisons in expressions use
+/// [`Value::compare`] which refuses to compare values of different kinds.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum Value {
     Boolean(bool),
     DateTime(DateTime<Utc>),
-    Decimal(BigDecimal),
-    Integer(BigInt),
+    Decimal(
+        #[cfg_attr(feature = "serde", serde(with = "crate::serialization::as_string"))] BigDecimal,
+    ),
+    Integer(#[cfg_attr(feature = "serde", serde(with = "crate::serialization::as_string"))] BigInt),
+    List(Vec<Value>),
+    Map(BTreeMap<String, Value>),
+    Money {
+        #[cfg_attr(feature = "serde", serde(with = "crate::serialization::as_string"))]
+        amount: BigDecimal,
+        currency: Currency,
+    },
+    Null,
+    Quantity {
+        magnitude: NumericValue,
+        unit: Unit,
+    },
     String(String),
 }
 
+impl Value {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Boolean(_) => "boolean",
+            Value::DateTime(_) => "date time",
+            Value::Decimal(_) => "decimal",
+            Value::Integer(_) => "integer",
+            Value::List(_) => "list",
+            Value::Map(_) => "map",
+            Value::Money { .. } => "money",
+            Value::Null => "null",
+            Value::Quantity { .. } => "quantity",
+            Value::String(_) => "string",
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, Value::Null)
+    }
+
+    /// Three-valued logical conjunction: false wins over null, null wins over true.
+    pub fn and(self, other: Value) -> Result<Value, DataError> {
+        match (self, other) {
+            (Value::Boolean(false), Value::Boolean(_) | Value::Null)
+            | (Value::Boolean(_) | Value::Null, Value::Boolean(false)) => Ok(Value::Boolean(false)),
+            (Value::Boolean(true), Value::Boolean(true)) => Ok(Value::Boolean(true)),
+            (Value::Boolean(true) | Value::Null, Value::Boolean(true) | Value::Null) => {
+                Ok(Value::Null)
+            }
+            (left, right) => Err(unsupported("and", &left, &right)),
+        }
+    }
+
+    /// Three-valued logical disjunction: true wins over null, null wins over false.
+    pub fn or(self, other: Value) -> Result<Value, DataError> {
+        match (self, other) {
+            (Value::Boolean(true), Value::Boolean(_) | Value::Null)
+            | (Value::Boolean(_) | Value::Null, Value::Boolean(true)) => Ok(Value::Boolean(true)),
+            (Value::Boolean(false), Value::Boolean(false)) => Ok(Value::Boolean(false)),
+            (Value::Boolean(false) | Value::Null, Value::Boolean(false) | Value::Null) => {
+                Ok(Value::Null)
+            }
+            (left, right) => Err(unsupported("or", &left, &right)),
+        }
+    }
+
+    /// Raises a number or a quantity to an integer power.
+    pub fn power(self, exponent: Value) -> Result<Value, DataError> {
+        let integer_exponent = match &exponent {
+            Value::Integer(i) => match i32::try_from(i) {
+                Ok(i) => Some(i),
+                Err(_) => {
+                    return Err(DataError::ExponentTooLarge {
+                        exponent: i.to_string(),
+                        max: MAX_EXPONENT,
+                    })
+                }
+            },
+            _ => None,
+        };
+        match (self, integer_exponent) {
+            (Value::Null, _) => Ok(Value::Null),
+            _ if exponent.is_null() => Ok(Value::Null),
+            (Value::Quantity { magnitude, unit }, Some(exponent)) => Ok(from_quantity(
+                Quantity::new(magnitude, unit).power(exponent)?,
+            )),
+            (base, Some(exponent)) => match base.to_numeric() {
+                Some(number) => Ok(Value::from(number.power(exponent)?)),
+                None => Err(unsupported("^", &base, &Value::Integer(exponent.into()))),
+            },
+            (base, None) => Err(unsupported("^", &base, &exponent)),
+        }
+    }
+
+    /// Tests list membership with SQL semantics: a value that is not found in a list that contains
+    /// null is unknown, so the result is null.
+    pub fn is_in(self, list: Value) -> Result<Value, DataError> {
+        match (self, list) {
+            (Value::Null, Value::List(_)) | (_, Value::Null) => Ok(Value::Null),
+            (value, Value::List(items)) => {
+                if items.contains(&value) {
+                    Ok(Value::Boolean(true))
+                } else if items.iter().any(Value::is_null) {
+                    Ok(Value::Null)
+                } else {
+                    Ok(Value::Boolean(false))
+                }
+            }
+            (value, list) => Err(unsupported("in", &value, &list)),
+        }
+    }
+
+    /// Returns this value, or the other one when this value is null.
+    pub fn coalesce(self, other: Value) -> Value {
+        match self {
+            Value::Null => other,
+            value => value,
+        }
+    }
+
+    /// Orders two values of the same kind. Numbers compare by value, money only within a currency
+    /// and quantities only within a dimension.
+    pub fn compare(&self, other: &Value) -> Result<Ordering, DataError> {
+        match (self, other) {
+            (Value::Boolean(left), Value::Boolean(right)) => Ok(left.cmp(right)),
+            (Value::DateTime(left), Value::DateTime(right)) => Ok(left.cmp(right)),
+            (Value::String(left), Value::String(right)) => Ok(left.cmp(right)),
+            (
+                Value::Money { amount, currency },
+                Value::Money {
+                    amount: a,
+                    currency: c,
+                },
+            ) => Money::new(amount.clone(), *currency).compare(&Money::new(a.clone(), *c)),
+            (Value::Quantity { .. }, _) | (_, Value::Quantity { .. }) => {
+                let (left, right) = quantity_operands("compare", self, other)?;
+                left.compare(&right)
+            }
+            (left, right) => {
+                let (left, right) = numeric_operands("compare", left, right)?;
+                Ok(left.compare(&right))
+            }
+        }
+    }
+
+    fn to_numeric(&self) -> Option<NumericValue> {
+        match self {
+            Value::Decimal(d) => Some(NumericValue::Decimal(d.clone())),
+            Value::Integer(i) => Some(NumericValue::Integer(i.clone())),
+            _ => None,
+        }
+    }
+
+    // Plain numbers take part in unit arithmetic as dimensionless quantities.
+    fn to_quantity(&self) -> Option<Quantity> {
+        match self {
+            Value::Quantity { magnitude, unit } => {
+                Some(Quantity::new(magnitude.clone(), unit.clone()))
+            }
+            _ => self
+                .to_numeric()
+                .map(|number| Quantity::new(number, Unit::base(Dimension::NONE))),
+        }
+    }
+}
+
+impl From<NumericValue> for Value {
+    fn from(number: NumericValue) -> Self {
+        match number {
+            NumericValue::Decimal(d) => Value::Decimal(d),
+            NumericValue::Integer(i) => Value::Integer(i),
+        }
+    }
+}
+
+impl From<Money> for Value {
+    fn from(money: Money) -> Self {
+        let (amount, currency) = money.into_parts();
+        Value::Money { amount, currency }
+    }
+}
+
+impl From<Quantity> for Value {
+    fn from(quantity: Quantity) -> Self {
+        let (magnitude, unit) = quantity.into_parts();
+        Value::Quantity { magnitude, unit }
+    }
+}
+
 impl Display for Value {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -19,7 +225,459 @@ impl Display for Value {
             Value::DateTime(d) => write!(f, "{}", d),
             Value::Decimal(d) => write!(f, "{}", d),
             Value::Integer(i) => write!(f, "{}", i),
+            Value::List(l) => {
+                write!(f, "[")?;
+                for (index, item) in l.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Value::Map(m) => {
+                write!(f, "{{")?;
+                for (index, (name, value)) in m.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", name, value)?;
+                }
+                write!(f, "}}")
+            }
+            Value::Money { amount, currency } => write!(f, "{} {}", currency, amount),
+            Value::Null => write!(f, "null"),
+            Value::Quantity { magnitude, unit } => write!(f, "{} {}", magnitude, unit),
             Value::String(s) => write!(f, "{}", s),
         }
     }
 }
+
+impl Value {
+    fn kind_rank(&self) -> u8 {
+        match self {
+            Value::Null => 0,
+            Value::Boolean(_) => 1,
+            Value::Decimal(_) | Value::Integer(_) => 2,
+            Value::Money { .. } => 3,
+            Value::Quantity { .. } => 4,
+            Value::String(_) => 5,
+            Value::DateTime(_) => 6,
+            Value::List(_) => 7,
+            Value::Map(_) => 8,
+        }
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Value {}
+
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Value {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Value::Boolean(left), Value::Boolean(right)) => left.cmp(right),
+            (Value::DateTime(left), Value::DateTime(right)) => left.cmp(right),
+            (Value::String(left), Value::String(right)) => left.cmp(right),
+            (Value::List(left), Value::List(right)) => left.cmp(right),
+            (Value::Map(left), Value::Map(right)) => left.iter().cmp(right.iter()),
+            (
+                Value::Money { amount, currency },
+                Value::Money {
+                    amount: a,
+                    currency: c,
+                },
+            ) => currency.code().cmp(c.code()).then_with(|| amount.cmp(a)),
+            (
+                Value::Quantity { magnitude, unit },
+                Value::Quantity {
+                    magnitude: m,
+                    unit: u,
+                },
+            ) => unit.dimension().cmp(&u.dimension()).then_with(|| {
+                let (left_numerator, left_denominator) = base_magnitude(magnitude, unit);
+                let (right_numerator, right_denominator) = base_magnitude(m, u);
+                (left_numerator * right_denominator).cmp(&(right_numerator * left_denominator))
+            }),
+            (left, right) => match (left.to_numeric(), right.to_numeric()) {
+                (Some(left), Some(right)) => left.compare(&right),
+                _ => left.kind_rank().cmp(&right.kind_rank()),
+            },
+        }
+    }
+}
+
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.kind_rank().hash(state);
+        match self {
+            Value::Boolean(b) => b.hash(state),
+            Value::DateTime(d) => d.hash(state),
+            Value::Decimal(d) => hash_decimal(d, state),
+            Value::Integer(i) => hash_decimal(&BigDecimal::from(i.clone()), state),
+            Value::List(items) => items.hash(state),
+            Value::Map(fields) => fields.hash(state),
+            Value::Money { amount, currency } => {
+                currency.code().hash(state);
+                hash_decimal(amount, state);
+            }
+            Value::Null => {}
+            Value::Quantity { magnitude, unit } => {
+                unit.dimension().hash(state);
+                base_magnitude(magnitude, unit).hash(state);
+            }
+            Value::String(s) => s.hash(state),
+        }
+    }
+}
+
+// Equal decimals with a different scale, e.g. `2` and `2.00`, hash the same.
+fn hash_decimal<H: Hasher>(decimal: &BigDecimal, state: &mut H) {
+    let (digits, scale) = decimal.normalized().into_bigint_and_exponent();
+    match digits.is_zero() {
+        true => digits.hash(state),
+        false => (digits, scale).hash(state),
+    }
+}
+
+// The magnitude of a quantity in the base unit of its dimension as a fraction in lowest terms with
+// a positive denominator.
+fn base_magnitude(magnitude: &NumericValue, unit: &Unit) -> (BigInt, BigInt) {
+    let (digits, scale) = match magnitude {
+        NumericValue::Decimal(d) => d.as_bigint_and_exponent(),
+        NumericValue::Integer(i) => (i.clone(), 0),
+    };
+    let (factor_numerator, factor_denominator) = unit.factor();
+    let mut numerator = digits * factor_numerator;
+    let mut denominator = factor_denominator.clone();
+    match scale >= 0 {
+        true => denominator *= BigInt::from(10).pow(scale as u32),
+        false => numerator *= BigInt::from(10).pow(scale.unsigned_abs() as u32),
+    }
+    let divisor = numerator.gcd(&denominator);
+    (numerator / &divisor, denominator / divisor)
+}
+
+fn unsupported(operation: &str, left: &Value, right: &Value) -> DataError {
+    DataError::UnsupportedOperation {
+        operation: operation.to_string(),
+        left: left.type_name().to_string(),
+        right: right.type_name().to_string(),
+    }
+}
+
+fn numeric_operands(
+    operation: &str,
+    left: &Value,
+    right: &Value,
+) -> Result<(NumericValue, NumericValue), DataError> {
+    match (left.to_numeric(), right.to_numeric()) {
+        (Some(left), Some(right)) => Ok((left, right)),
+        _ => Err(unsupported(operation, left, right)),
+    }
+}
+
+fn quantity_operands(
+    operation: &str,
+    left: &Value,
+    right: &Value,
+) -> Result<(Quantity, Quantity), DataError> {
+    match (left.to_quantity(), right.to_quantity()) {
+        (Some(left), Some(right)) => Ok((left, right)),
+        _ => Err(unsupported(operation, left, right)),
+    }
+}
+
+// Quantities whose units cancel out are turned back into plain numbers.
+fn from_quantity(quantity: Quantity) -> Value {
+    match quantity.dimensionless_magnitude() {
+        Some(number) => Value::from(number),
+        None => Value::from(quantity),
+    }
+}
+
+// Arithmetic on null results in null, like it does in SQL.
+impl Add for Value {
+    type Output = Result<Value, DataError>;
+
+    fn add(self, other: Value) -> Self::Output {
+        match (self, other) {
+            (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
+            (
+                Value::Money { amount, currency },
+                Value::Money {
+                    amount: a,
+                    currency: c,
+                },
+            ) => Ok((Money::new(amount, currency) + Money::new(a, c))?.into()),
+            (left @ Value::Quantity { .. }, right) | (left, right @ Value::Quantity { .. }) => {
+                let (left, right) = quantity_operands("+", &left, &right)?;
+                Ok(from_quantity((left + right)?))
+            }
+            (Value::String(left), Value::String(right)) => Ok(Value::String(left + &right)),
+            (left, right) => {
+                let (left, right) = numeric_operands("+", &left, &right)?;
+                Ok(Value::from(left + right))
+            }
+        }
+    }
+}
+
+impl Sub for Value {
+    type Output = Result<Value, DataError>;
+
+    fn sub(self, other: Value) -> Self::Output {
+        match (self, other) {
+            (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
+            (
+                Value::Money { amount, currency },
+                Value::Money {
+                    amount: a,
+                    currency: c,
+                },
+            ) => Ok((Money::new(amount, currency) - Money::new(a, c))?.into()),
+            (left @ Value::Quantity { .. }, right) | (left, right @ Value::Quantity { .. }) => {
+                let (left, right) = quantity_operands("-", &left, &right)?;
+                Ok(from_quantity((left - right)?))
+            }
+            (left, right) => {
+                let (left, right) = numeric_operands("-", &left, &right)?;
+                Ok(Value::from(left - right))
+            }
+        }
+    }
+}
+
+impl Mul for Value {
+    type Output = Result<Value, DataError>;
+
+    fn mul(self, other: Value) -> Self::Output {
+        match (self, other) {
+            (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
+            (money @ Value::Money { .. }, factor) | (factor, money @ Value::Money { .. }) => {
+                match (money, factor.to_numeric()) {
+                    (Value::Money { amount, currency }, Some(factor)) => {
+                        Ok(Money::new(amount, currency).multiply(factor).into())
+                    }
+                    (money, _) => Err(unsupported("*", &money, &factor)),
+                }
+            }
+            (left @ Value::Quantity { .. }, right) | (left, right @ Value::Quantity { .. }) => {
+                let (left, right) = quantity_operands("*", &left, &right)?;
+                Ok(from_quantity(left * right))
+            }
+            (left, right) => {
+                let (left, right) = numeric_operands("*", &left, &right)?;
+                Ok(Value::from(left * right))
+            }
+        }
+    }
+}
+
+/// Three-valued logical negation: the negation of null is null.
+impl Not for Value {
+    type Output = Result<Value, DataError>;
+
+    fn not(self) -> Self::Output {
+        match self {
+            Value::Boolean(b) => Ok(Value::Boolean(!b)),
+            Value::Null => Ok(Value::Null),
+            value => Err(DataError::UnsupportedOperation {
+                operation: "not".to_string(),
+                left: value.type_name().to_string(),
+                right: value.type_name().to_string(),
+            }),
+        }
+    }
+}
+
+impl Div for Value {
+    type Output = Result<Value, DataError>;
+
+    fn div(self, other: Value) -> Self::Output {
+        match (self, other) {
+            (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
+            (
+                Value::Money { amount, currency },
+                Value::Money {
+                    amount: a,
+                    currency: c,
+                },
+            ) => Ok(Value::from(
+                Money::new(amount, currency).ratio(Money::new(a, c))?,
+            )),
+            (Value::Money { amount, currency }, divisor) => match divisor.to_numeric() {
+                Some(divisor) => Ok(Money::new(amount, currency).divide(divisor)?.into()),
+                None => Err(unsupported(
+                    "/",
+                    &Value::Money { amount, currency },
+                    &divisor,
+                )),
+            },
+            (left @ Value::Quantity { .. }, right) | (left, right @ Value::Quantity { .. }) => {
+                let (left, right) = quantity_operands("/", &left, &right)?;
+                Ok(from_quantity((left / right)?))
+            }
+            (left, right) => {
+                let (left, right) = numeric_operands("/", &left, &right)?;
+                if right.is_zero() {
+                    return Err(DataError::DivisionByZero);
+                }
+                Ok(Value::from(left / right))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unit::UnitRegistry;
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::HashSet;
+    use std::str::FromStr;
+
+    fn decimal(value: &str) -> Value {
+        Value::Decimal(BigDecimal::from_str(value).unwrap())
+    }
+
+    fn quantity(magnitude: &str, unit: &str) -> Value {
+        Value::from(Quantity::new(
+            NumericValue::try_from(magnitude).unwrap(),
+            UnitRegistry::standard().parse(unit).unwrap(),
+        ))
+    }
+
+    fn hash(value: &Value) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_equality_across_representations() {
+        let pairs = [
+            (Value::Integer(BigInt::from(2)), decimal("2.00")),
+            (decimal("0"), decimal("0.000")),
+            (quantity("1", "km"), quantity("1000", "m")),
+            (quantity("36", "km/h"), quantity("10", "m/s")),
+            (
+                Value::List(vec![Value::Integer(BigInt::from(1))]),
+                Value::List(vec![decimal("1.0")]),
+            ),
+        ];
+        for (left, right) in pairs {
+            assert_eq!(left, right);
+            assert_eq!(hash(&left), hash(&right), "{} and {}", left, right);
+        }
+        assert_ne!(Value::String("2".into()), Value::Integer(BigInt::from(2)));
+        assert_ne!(quantity("1", "kg"), quantity("1", "m"));
+    }
+
+    #[test]
+    fn test_power() {
+        let power = |base: Value, exponent: i64| base.power(Value::Integer(BigInt::from(exponent)));
+        assert_eq!(power(decimal("1.5"), 3).unwrap().to_string(), "3.375");
+        assert_eq!(
+            power(Value::Integer(BigInt::from(2)), 100).unwrap(),
+            Value::Integer(BigInt::from(2).pow(100))
+        );
+        assert_eq!(
+            power(Value::Integer(BigInt::from(2)), -2).unwrap(),
+            decimal("0.25")
+        );
+        assert_eq!(
+            power(quantity("3", "km"), 2).unwrap(),
+            quantity("9000000", "m^2")
+        );
+        for base in [Value::Integer(BigInt::from(1)), quantity("1", "m")] {
+            assert!(matches!(
+                power(base, 200_000_000),
+                Err(DataError::ExponentTooLarge { .. })
+            ));
+        }
+        assert!(matches!(
+            power(decimal("1.0"), i64::MAX),
+            Err(DataError::ExponentTooLarge { .. })
+        ));
+    }
+
+    #[test]
+    fn test_total_order_across_kinds() {
+        let mut values = [
+            Value::String("a".into()),
+            decimal("1.5"),
+            Value::Null,
+            Value::List(vec![]),
+            Value::Integer(BigInt::from(1)),
+            Value::Boolean(true),
+            Value::Integer(BigInt::from(2)),
+        ];
+        values.sort();
+        let sorted: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+        assert_eq!(sorted, ["null", "true", "1", "1.5", "2", "a", "[]"]);
+        let distinct: HashSet<Value> = [decimal("2.0"), Value::Integer(BigInt::from(2))]
+            .into_iter()
+            .collect();
+        assert_eq!(distinct.len(), 1);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_is_lossless() {
+        let values = [
+            Value::Decimal(BigDecimal::from_str("12345678901234567890.123456789").unwrap()),
+            Value::Integer(BigInt::from_str("123456789012345678901234567890").unwrap()),
+            Value::DateTime(DateTime::from_str("2024-02-29T12:30:00Z").unwrap()),
+            Value::from(Money::new(
+                BigDecimal::from_str("12.50").unwrap(),
+                Currency::from_code("EUR").unwrap(),
+            )),
+            Value::from(Quantity::new(
+                NumericValue::Integer(BigInt::from(36)),
+                UnitRegistry::standard().parse("km/h").unwrap(),
+            )),
+            Value::Map(BTreeMap::from([(
+                "items".to_string(),
+                Value::List(vec![
+                    Value::Null,
+                    Value::Boolean(true),
+                    Value::String("a".into()),
+                ]),
+            )])),
+        ];
+        for value in values {
+            let json = serde_json::to_string(&value).unwrap();
+            let deserialized: Value = serde_json::from_str(&json).unwrap();
+            assert_eq!(deserialized, value, "json = {}", json);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_format() {
+        let value = Value::from(Money::new(
+            BigDecimal::from_str("0.10").unwrap(),
+            Currency::from_code("USD").unwrap(),
+        ));
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(json, r#"{"money":{"amount":"0.10","currency":"USD"}}"#);
+        let json = serde_json::to_string(&Value::DateTime(
+            DateTime::from_str("2024-02-29T12:30:00Z").unwrap(),
+        ))
+        .unwrap();
+        assert_eq!(json, r#"{"date_time":"2024-02-29T12:30:00Z"}"#);
+        let result = serde_json::from_str::<Value>(r#"{"money":{"amount":"1","currency":"XYZ"}}"#);
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,177 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, parse_quote, Data, DeriveInput, Error, Fields, LitStr};
+
+/// Derives `ExpressiveContext` and `ToValue` for a struct with named fields, so that its fields can
+/// be used as the variables of an expression or as the fields of a record value.
+///
+/// Fields are named after the Rust field unless renamed with `#[expressive(rename = "name")]`, and
+/// fields marked with `#[expressive(skip)]` are left out.
+#[proc_macro_derive(ExpressiveContext, attributes(expressive))]
+pub fn derive_expressive_context(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+struct FieldOptions {
+    rename: Option<String>,
+    skip: bool,
+}
+
+fn field_options(field: &syn::Field) -> Result<FieldOptions, Error> {
+    let mut options = FieldOptions {
+        rename: None,
+        skip: false,
+    };
+    for attribute in field
+        .attrs
+        .iter()
+        .filter(|a| a.path().is_ident("expressive"))
+    {
+        attribute.parse_nested_meta(|meta| {
+            if meta.path.is_ident("skip") {
+                options.skip = true;
+                Ok(())
+            } else if meta.path.is_ident("rename") {
+                let name: LitStr = meta.value()?.parse()?;
+                options.rename = Some(name.value());
+                Ok(())
+            } else {
+                Err(meta.error("expected `rename = \"...\"` or `skip`"))
+            }
+        })?;
+    }
+    Ok(options)
+}
+
+fn expand(mut input: DeriveInput) -> Result<TokenStream2, Error> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new_spanned(
+                    &input.ident,
+                    "ExpressiveContext can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "ExpressiveContext can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut inserts = Vec::new();
+    for field in fields {
+        let options = field_options(field)?;
+        if options.skip {
+            continue;
+        }
+        let ident = field
+            .ident
+            .as_ref()
+            .expect("named fields have an identifier");
+        let name = options
+            .rename
+            .unwrap_or_else(|| ident.to_string().trim_start_matches("r#").to_owned());
+        inserts.push(quote! {
+            variables.insert(
+                ::std::string::String::from(#name),
+                ::expressive_data::context::ToValue::to_value(&self.#ident),
+            );
+        });
+    }
+
+    let type_parameters: Vec<_> = input
+        .generics
+        .type_params()
+        .map(|parameter| parameter.ident.clone())
+        .collect();
+    let where_clause = input.generics.make_where_clause();
+    for parameter in type_parameters {
+        where_clause
+            .predicates
+            .push(parse_quote!(#parameter: ::expressive_data::context::ToValue));
+    }
+    let ident = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::expressive_data::context::ExpressiveContext for #ident #type_generics
+        #where_clause
+        {
+            fn variables(
+                &self,
+            ) -> ::std::collections::HashMap<::std::string::String, ::expressive_data::value::Value>
+            {
+                let mut variables = ::std::collections::HashMap::new();
+                #(#inserts)*
+                variables
+            }
+        }
+
+        impl #impl_generics ::expressive_data::context::ToValue for #ident #type_generics
+        #where_clause
+        {
+            fn to_value(&self) -> ::expressive_data::value::Value {
+                ::expressive_data::value::Value::Map(
+                    ::expressive_data::context::ExpressiveContext::variables(self)
+                        .into_iter()
+                        .collect(),
+                )
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand_to_string(input: DeriveInput) -> String {
+        expand(input)
+            .map(|tokens| tokens.to_string())
+            .unwrap_or_else(|error| error.to_string())
+    }
+
+    #[test]
+    fn test_rename_and_skip() {
+        let expanded = expand_to_string(parse_quote! {
+            struct Customer<T> {
+                #[expressive(rename = "customer_name")]
+                name: String,
+                r#type: T,
+                #[expressive(skip)]
+                password: String,
+            }
+        });
+        assert!(expanded.contains(r#"String :: from ("customer_name")"#));
+        assert!(expanded.contains(r#"String :: from ("type")"#));
+        assert!(!expanded.contains("password"));
+        assert!(expanded.contains("T : :: expressive_data :: context :: ToValue"));
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        let expanded = expand_to_string(parse_quote! {
+            struct Pair(u32, u32);
+        });
+        assert!(expanded.contains("structs with named fields"));
+        let expanded = expand_to_string(parse_quote! {
+            enum Status { Open }
+        });
+        assert!(expanded.contains("can only be derived for structs"));
+        let expanded = expand_to_string(parse_quote! {
+            struct Customer {
+                #[expressive(hidden)]
+                name: String,
+            }
+        });
+        assert!(expanded.contains("expected `rename"));
+    }
+}
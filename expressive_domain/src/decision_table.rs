@@ -0,0 +1,909 @@
+use crate::error::DomainError;
+use expressive_data::value::Value;
+use expressive_expression::ast::{BinaryOperator, Expression};
+use expressive_expression::evaluate::{evaluate_expression, EvaluationOptions};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+
+/// Decides the result of a decision table when more than one rule matches.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum HitPolicy {
+    /// At most one rule may match, more matches are an error.
+    Unique,
+    /// The first matching rule wins.
+    First,
+    /// The matching rule whose outputs come first in the priorities of the output columns wins.
+    Priority,
+    /// The outputs of all matching rules, as a list or aggregated into a single value.
+    Collect(Option<Aggregation>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Aggregation {
+    Sum,
+    Min,
+    Max,
+}
+
+/// An input column tests the value of an expression, e.g. `order.amount`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InputColumn {
+    pub name: String,
+    pub expression: String,
+}
+
+/// An output column with its values from the highest to the lowest priority, which only the
+/// priority hit policy uses. Values that are not listed have the lowest priority.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OutputColumn {
+    pub name: String,
+    pub priorities: Vec<Value>,
+}
+
+/// A rule with a test for every input column and an expression for every output column.
+///
+/// A test is `-` or empty to match anything, a comparison such as `> 100` or `!= "NL"`, a range
+/// such as `[10..20]` or `[10..20)` where square brackets include the endpoint, a comma separated
+/// list of values such as `"NL", "BE"`, or a single value that the input must equal.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DecisionRule {
+    pub tests: Vec<String>,
+    pub outputs: Vec<String>,
+}
+
+/// A decision table in the spirit of DMN, where every row is a rule that maps tests on the inputs
+/// to the outputs.
+///
+/// A table compiles to expressions: the tests of a rule to a single condition and its outputs to an
+/// expression each. The result of a table with a single output column is the value of that column,
+/// with more output columns it is a map by column name. When no rule matches the result is null.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DecisionTable {
+    pub name: String,
+    pub hit_policy: HitPolicy,
+    pub inputs: Vec<InputColumn>,
+    pub outputs: Vec<OutputColumn>,
+    pub rules: Vec<DecisionRule>,
+}
+
+/// A decision table with its rules compiled to expressions, to evaluate it many times.
+#[derive(Clone, Debug)]
+pub struct CompiledDecisionTable {
+    name: String,
+    hit_policy: HitPolicy,
+    outputs: Vec<OutputColumn>,
+    rules: Vec<CompiledRule>,
+}
+
+#[derive(Clone, Debug)]
+struct CompiledRule {
+    condition: Expression,
+    outputs: Vec<Expression>,
+}
+
+/// The result of checking the rules of a table, by rule index.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TableCheck {
+    /// The pairs of rules that match the same inputs.
+    pub overlaps: Vec<(usize, usize)>,
+    /// The inputs that no rule matches, described as a test per input column.
+    pub gaps: Vec<Vec<String>>,
+}
+
+// A parsed test of a rule on the value of its input column.
+#[derive(Clone, Debug)]
+enum Test {
+    Any,
+    Compare(BinaryOperator, Expression),
+    Range {
+        low: Expression,
+        low_inclusive: bool,
+        high: Expression,
+        high_inclusive: bool,
+    },
+    OneOf(Vec<Expression>),
+}
+
+impl DecisionTable {
+    pub fn new(name: impl Into<String>, hit_policy: HitPolicy) -> DecisionTable {
+        DecisionTable {
+            name: name.into(),
+            hit_policy,
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            rules: Vec::new(),
+        }
+    }
+
+    pub fn with_input(
+        mut self,
+        name: impl Into<String>,
+        expression: impl Into<String>,
+    ) -> DecisionTable {
+        self.inputs.push(InputColumn {
+            name: name.into(),
+            expression: expression.into(),
+        });
+        self
+    }
+
+    pub fn with_output(self, name: impl Into<String>) -> DecisionTable {
+        self.with_prioritized_output(name, Vec::new())
+    }
+
+    pub fn with_prioritized_output(
+        mut self,
+        name: impl Into<String>,
+        priorities: Vec<Value>,
+    ) -> DecisionTable {
+        self.outputs.push(OutputColumn {
+            name: name.into(),
+            priorities,
+        });
+        self
+    }
+
+    pub fn with_rule(mut self, tests: &[&str], outputs: &[&str]) -> DecisionTable {
+        self.rules.push(DecisionRule {
+            tests: tests.iter().map(|test| test.to_string()).collect(),
+            outputs: outputs.iter().map(|output| output.to_string()).collect(),
+        });
+        self
+    }
+
+    fn invalid(&self, message: String) -> DomainError {
+        DomainError::InvalidDecisionTable {
+            name: self.name.clone(),
+            message,
+        }
+    }
+
+    fn parse(&self, source: &str) -> Result<Expression, DomainError> {
+        source
+            .parse()
+            .map_err(|error| self.invalid(format!("could not parse '{}': {}", source, error)))
+    }
+
+    // Parses the tests of all rules, by rule and then by input column.
+    fn tests(&self) -> Result<Vec<Vec<Test>>, DomainError> {
+        self.rules
+            .iter()
+            .enumerate()
+            .map(|(index, rule)| {
+                if rule.tests.len() != self.inputs.len() {
+                    return Err(self.invalid(format!(
+                        "rule {} has {} tests for {} input columns",
+                        index,
+                        rule.tests.len(),
+                        self.inputs.len()
+                    )));
+                }
+                rule.tests
+                    .iter()
+                    .map(|test| self.parse_test(test))
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn parse_test(&self, test: &str) -> Result<Test, DomainError> {
+        let test = test.trim();
+        if test.is_empty() || test == "-" {
+            return Ok(Test::Any);
+        }
+        let comparisons = [
+            ("==", BinaryOperator::Equal),
+            ("!=", BinaryOperator::NotEqual),
+            (">=", BinaryOperator::GreaterOrEqual),
+            ("<=", BinaryOperator::LessOrEqual),
+            (">", BinaryOperator::Greater),
+            ("<", BinaryOperator::Less),
+        ];
+        for (symbol, operator) in comparisons {
+            if let Some(operand) = test.strip_prefix(symbol) {
+                return Ok(Test::Compare(operator, self.parse(operand)?));
+            }
+        }
+        if let Some(range) = self.parse_range(test)? {
+            return Ok(range);
+        }
+        let values = split_top_level(test);
+        match values.as_slice() {
+            [value] => Ok(Test::Compare(BinaryOperator::Equal, self.parse(value)?)),
+            values => Ok(Test::OneOf(
+                values
+                    .iter()
+                    .map(|value| self.parse(value))
+                    .collect::<Result<_, _>>()?,
+            )),
+        }
+    }
+
+    // A range has the form `[low..high]`, where a round or reversed square bracket excludes the
+    // endpoint, e.g. `(10..20]` or `]10..20]`.
+    fn parse_range(&self, test: &str) -> Result<Option<Test>, DomainError> {
+        let mut characters = test.chars();
+        let (Some(open), Some(close)) = (characters.next(), characters.next_back()) else {
+            return Ok(None);
+        };
+        let Some((low, high)) = characters.as_str().split_once("..") else {
+            return Ok(None);
+        };
+        let low_inclusive = match open {
+            '[' => true,
+            '(' | ']' => false,
+            _ => return Ok(None),
+        };
+        let high_inclusive = match close {
+            ']' => true,
+            ')' | '[' => false,
+            _ => return Ok(None),
+        };
+        Ok(Some(Test::Range {
+            low: self.parse(low)?,
+            low_inclusive,
+            high: self.parse(high)?,
+            high_inclusive,
+        }))
+    }
+
+    /// Compiles the tests of every rule to a condition and its outputs to expressions.
+    pub fn compile(&self) -> Result<CompiledDecisionTable, DomainError> {
+        if self.outputs.is_empty() {
+            return Err(self.invalid("the table has no output columns".to_string()));
+        }
+        if matches!(self.hit_policy, HitPolicy::Collect(Some(_))) && self.outputs.len() > 1 {
+            return Err(
+                self.invalid("only a table with a single output column can aggregate".to_string())
+            );
+        }
+        let inputs = self
+            .inputs
+            .iter()
+            .map(|input| self.parse(&input.expression))
+            .collect::<Result<Vec<Expression>, DomainError>>()?;
+        let tests = self.tests()?;
+        let mut rules = Vec::with_capacity(self.rules.len());
+        for (index, (rule, tests)) in self.rules.iter().zip(tests).enumerate() {
+            if rule.outputs.len() != self.outputs.len() {
+                return Err(self.invalid(format!(
+                    "rule {} has {} outputs for {} output columns",
+                    index,
+                    rule.outputs.len(),
+                    self.outputs.len()
+                )));
+            }
+            let condition = inputs
+                .iter()
+                .zip(tests)
+                .filter_map(|(input, test)| condition(input, test))
+                .reduce(|left, right| binary(BinaryOperator::And, left, right))
+                .unwrap_or(Expression::Literal {
+                    value: Value::Boolean(true),
+                });
+            let outputs = rule
+                .outputs
+                .iter()
+                .map(|output| self.parse(output))
+                .collect::<Result<_, _>>()?;
+            rules.push(CompiledRule { condition, outputs });
+        }
+        Ok(CompiledDecisionTable {
+            name: self.name.clone(),
+            hit_policy: self.hit_policy,
+            outputs: self.outputs.clone(),
+            rules,
+        })
+    }
+
+    pub fn evaluate(&self, variables: &HashMap<String, Value>) -> Result<Value, DomainError> {
+        self.compile()?.evaluate(variables)
+    }
+
+    /// Finds the rules that overlap and the inputs that no rule matches.
+    ///
+    /// The check divides the values of every input column at the values its tests mention. Two
+    /// rules overlap when, for every column, some segment satisfies the tests of both, which takes
+    /// time quadratic in the number of rules. The gaps are found by dividing the inputs column by
+    /// column and following only the rules that match so far, which stops as soon as no rule or a
+    /// rule for all remaining values matches. Since tables can have exponentially many gaps, the
+    /// check gives up with an error after looking at [`MAX_CHECKED_CELLS`] combinations of
+    /// segments. It can only check tests whose values are literals.
+    pub fn check(&self) -> Result<TableCheck, DomainError> {
+        let tests = self.tests()?;
+        let mut columns = Vec::with_capacity(self.inputs.len());
+        for (column, input) in self.inputs.iter().enumerate() {
+            let mut points = Vec::new();
+            for rule in &tests {
+                for value in self.literals(&rule[column])? {
+                    points.push(value);
+                }
+            }
+            points.sort();
+            points.dedup();
+            columns.push((input, segments(points)));
+        }
+        let mut check = TableCheck::default();
+        for (first, first_tests) in tests.iter().enumerate() {
+            for (second, second_tests) in tests.iter().enumerate().skip(first + 1) {
+                let overlap = columns
+                    .iter()
+                    .zip(first_tests.iter().zip(second_tests))
+                    .all(|((_, segments), (first_test, second_test))| {
+                        segments.iter().any(|segment| {
+                            segment.satisfies(first_test) && segment.satisfies(second_test)
+                        })
+                    });
+                if overlap {
+                    check.overlaps.push((first, second));
+                }
+            }
+        }
+        let mut search = GapSearch {
+            columns: &columns,
+            tests: &tests,
+            cell: Vec::with_capacity(columns.len()),
+            visited: 0,
+            gaps: Vec::new(),
+        };
+        if !search.find((0..tests.len()).collect()) {
+            return Err(self.invalid(format!(
+                "the table has more than {} combinations of input values to check for gaps",
+                MAX_CHECKED_CELLS
+            )));
+        }
+        check.gaps = search.gaps;
+        Ok(check)
+    }
+
+    fn literals(&self, test: &Test) -> Result<Vec<Value>, DomainError> {
+        let expressions = match test {
+            Test::Any => vec![],
+            Test::Compare(_, expression) => vec![expression],
+            Test::Range { low, high, .. } => vec![low, high],
+            Test::OneOf(expressions) => expressions.iter().collect(),
+        };
+        expressions
+            .into_iter()
+            .map(|expression| match expression {
+                Expression::Literal { value } => Ok(value.clone()),
+                expression => Err(self.invalid(format!(
+                    "the test value '{}' is not a literal and cannot be checked",
+                    expression
+                ))),
+            })
+            .collect()
+    }
+}
+
+fn binary(operator: BinaryOperator, left: Expression, right: Expression) -> Expression {
+    Expression::Binary {
+        operator,
+        left: Box::new(left),
+        right: Box::new(right),
+    }
+}
+
+fn condition(input: &Expression, test: Test) -> Option<Expression> {
+    match test {
+        Test::Any => None,
+        Test::Compare(operator, value) => Some(binary(operator, input.clone(), value)),
+        Test::Range {
+            low,
+            low_inclusive,
+            high,
+            high_inclusive,
+        } => {
+            let low_operator = match low_inclusive {
+                true => BinaryOperator::GreaterOrEqual,
+                false => BinaryOperator::Greater,
+            };
+            let high_operator = match high_inclusive {
+                true => BinaryOperator::LessOrEqual,
+                false => BinaryOperator::Less,
+            };
+            Some(binary(
+                BinaryOperator::And,
+                binary(low_operator, input.clone(), low),
+                binary(high_operator, input.clone(), high),
+            ))
+        }
+        Test::OneOf(items) => Some(binary(
+            BinaryOperator::In,
+            input.clone(),
+            Expression::List { items },
+        )),
+    }
+}
+
+// Splits a test at the commas that are not inside a string, list or function call.
+fn split_top_level(test: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut quoted, mut start) = (0, false, 0);
+    for (index, character) in test.char_indices() {
+        match character {
+            '"' => quoted = !quoted,
+            '(' | '[' if !quoted => depth += 1,
+            ')' | ']' if !quoted => depth -= 1,
+            ',' if !quoted && depth == 0 => {
+                parts.push(test[start..index].trim());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(test[start..].trim());
+    parts
+}
+
+/// The largest number of combinations of input segments that [`DecisionTable::check`] looks at to
+/// find the gaps of a table.
+pub const MAX_CHECKED_CELLS: usize = 100_000;
+
+struct GapSearch<'a> {
+    columns: &'a [(&'a InputColumn, Vec<Segment>)],
+    tests: &'a [Vec<Test>],
+    cell: Vec<&'a Segment>,
+    visited: usize,
+    gaps: Vec<Vec<String>>,
+}
+
+impl<'a> GapSearch<'a> {
+    // Divides the segments of the next column among the rules that match the cell chosen so far. A
+    // cell that no rule matches is a gap that matches anything in the remaining columns. Returns
+    // false when the search looked at too many cells.
+    fn find(&mut self, rules: Vec<usize>) -> bool {
+        let column = self.cell.len();
+        if rules.is_empty() {
+            let gap = self
+                .columns
+                .iter()
+                .enumerate()
+                .map(|(index, (input, _))| match self.cell.get(index) {
+                    Some(segment) => segment.describe(&input.name),
+                    None => "-".to_string(),
+                })
+                .collect();
+            self.gaps.push(gap);
+            return true;
+        }
+        let covers_rest = |rule: &usize| {
+            self.columns[column..]
+                .iter()
+                .zip(&self.tests[*rule][column..])
+                .all(|((_, segments), test)| segments.iter().all(|segment| segment.satisfies(test)))
+        };
+        if rules.iter().any(covers_rest) {
+            return true;
+        }
+        let columns = self.columns;
+        for segment in &columns[column].1 {
+            self.visited += 1;
+            if self.visited > MAX_CHECKED_CELLS {
+                return false;
+            }
+            let matching = rules
+                .iter()
+                .copied()
+                .filter(|rule| segment.satisfies(&self.tests[*rule][column]))
+                .collect();
+            self.cell.push(segment);
+            let finished = self.find(matching);
+            self.cell.pop();
+            if !finished {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+// The values of an input column divided at the values that its tests mention, which no test can
+// tell apart within a segment.
+enum Segment {
+    All,
+    Below(Value),
+    Point(Value),
+    Between(Value, Value),
+    Above(Value),
+}
+
+fn segments(points: Vec<Value>) -> Vec<Segment> {
+    let (Some(first), Some(last)) = (points.first(), points.last()) else {
+        return vec![Segment::All];
+    };
+    let mut segments = vec![Segment::Below(first.clone())];
+    for (index, point) in points.iter().enumerate() {
+        segments.push(Segment::Point(point.clone()));
+        if let Some(next) = points.get(index + 1) {
+            segments.push(Segment::Between(point.clone(), next.clone()));
+        }
+    }
+    segments.push(Segment::Above(last.clone()));
+    segments
+}
+
+impl Segment {
+    // Only called for tests whose values are literals, as checked when the segments were made.
+    fn satisfies(&self, test: &Test) -> bool {
+        let literal = |expression: &Expression| match expression {
+            Expression::Literal { value } => Some(value.clone()),
+            _ => None,
+        };
+        match test {
+            Test::Any => true,
+            Test::Compare(operator, value) => literal(value)
+                .map(|value| self.compares(*operator, &value))
+                .unwrap_or(false),
+            Test::Range {
+                low,
+                low_inclusive,
+                high,
+                high_inclusive,
+            } => {
+                let low_operator = match low_inclusive {
+                    true => BinaryOperator::GreaterOrEqual,
+                    false => BinaryOperator::Greater,
+                };
+                let high_operator = match high_inclusive {
+                    true => BinaryOperator::LessOrEqual,
+                    false => BinaryOperator::Less,
+                };
+                match (literal(low), literal(high)) {
+                    (Some(low), Some(high)) => {
+                        self.compares(low_operator, &low) && self.compares(high_operator, &high)
+                    }
+                    _ => false,
+                }
+            }
+            Test::OneOf(values) => values
+                .iter()
+                .filter_map(literal)
+                .any(|value| self.compares(BinaryOperator::Equal, &value)),
+        }
+    }
+
+    // Whether all values in the segment compare to the value, which is one of the points the
+    // segments are divided at, so that a segment is either entirely on one side of it or on it.
+    fn compares(&self, operator: BinaryOperator, value: &Value) -> bool {
+        let accept = |ordering: Ordering| match operator {
+            BinaryOperator::Equal => ordering == Ordering::Equal,
+            BinaryOperator::NotEqual => ordering != Ordering::Equal,
+            BinaryOperator::Less => ordering == Ordering::Less,
+            BinaryOperator::LessOrEqual => ordering != Ordering::Greater,
+            BinaryOperator::Greater => ordering == Ordering::Greater,
+            BinaryOperator::GreaterOrEqual => ordering != Ordering::Less,
+            _ => false,
+        };
+        match self {
+            Segment::All => accept(Ordering::Less) && accept(Ordering::Greater),
+            Segment::Point(point) => accept(point.cmp(value)),
+            Segment::Below(upper) => upper <= value && accept(Ordering::Less),
+            Segment::Above(lower) => lower >= value && accept(Ordering::Greater),
+            Segment::Between(lower, upper) => match (lower >= value, upper <= value) {
+                (true, _) => accept(Ordering::Greater),
+                (_, true) => accept(Ordering::Less),
+                _ => false,
+            },
+        }
+    }
+
+    fn describe(&self, name: &str) -> String {
+        let literal = |value: &Value| {
+            Expression::Literal {
+                value: value.clone(),
+            }
+            .to_string()
+        };
+        match self {
+            Segment::All => "-".to_string(),
+            Segment::Below(upper) => format!("{} < {}", name, literal(upper)),
+            Segment::Point(point) => format!("{} == {}", name, literal(point)),
+            Segment::Between(lower, upper) => format!(
+                "{} > {} and {} < {}",
+                name,
+                literal(lower),
+                name,
+                literal(upper)
+            ),
+            Segment::Above(lower) => format!("{} > {}", name, literal(lower)),
+        }
+    }
+}
+
+impl CompiledDecisionTable {
+    /// The condition that the tests of every rule compiled to.
+    pub fn conditions(&self) -> impl Iterator<Item = &Expression> {
+        self.rules.iter().map(|rule| &rule.condition)
+    }
+
+    pub fn evaluate(&self, variables: &HashMap<String, Value>) -> Result<Value, DomainError> {
+        let options = EvaluationOptions::default();
+        let evaluate = |expression: &Expression| {
+            evaluate_expression(expression, variables, &options).map_err(|source| {
+                DomainError::EvaluationFailed {
+                    name: self.name.clone(),
+                    source,
+                }
+            })
+        };
+        let mut matches = Vec::new();
+        for (index, rule) in self.rules.iter().enumerate() {
+            if evaluate(&rule.condition)? == Value::Boolean(true) {
+                matches.push(index);
+                if self.hit_policy == HitPolicy::First {
+                    break;
+                }
+            }
+        }
+        let mut results = Vec::with_capacity(matches.len());
+        for index in &matches {
+            let outputs = self.rules[*index]
+                .outputs
+                .iter()
+                .map(evaluate)
+                .collect::<Result<Vec<Value>, DomainError>>()?;
+            results.push(outputs);
+        }
+        match self.hit_policy {
+            HitPolicy::Unique if matches.len() > 1 => Err(DomainError::HitPolicyViolation {
+                name: self.name.clone(),
+                rules: matches,
+            }),
+            HitPolicy::Unique | HitPolicy::First => Ok(results
+                .into_iter()
+                .next()
+                .map(|outputs| self.result(outputs))
+                .unwrap_or(Value::Null)),
+            HitPolicy::Priority => Ok(results
+                .into_iter()
+                .min_by_key(|outputs| self.priority(outputs))
+                .map(|outputs| self.result(outputs))
+                .unwrap_or(Value::Null)),
+            HitPolicy::Collect(None) => Ok(Value::List(
+                results
+                    .into_iter()
+                    .map(|outputs| self.result(outputs))
+                    .collect(),
+            )),
+            HitPolicy::Collect(Some(aggregation)) => {
+                let values = results
+                    .into_iter()
+                    .flatten()
+                    .filter(|value| !value.is_null());
+                aggregate(aggregation, values)
+            }
+        }
+    }
+
+    fn result(&self, outputs: Vec<Value>) -> Value {
+        match <[Value; 1]>::try_from(outputs) {
+            Ok([value]) => value,
+            Err(outputs) => Value::Map(
+                self.outputs
+                    .iter()
+                    .map(|column| column.name.clone())
+                    .zip(outputs)
+                    .collect::<BTreeMap<String, Value>>(),
+            ),
+        }
+    }
+
+    // The position of every output in the priorities of its column, where lower comes first.
+    fn priority(&self, outputs: &[Value]) -> Vec<usize> {
+        self.outputs
+            .iter()
+            .zip(outputs)
+            .map(|(column, output)| {
+                column
+                    .priorities
+                    .iter()
+                    .position(|priority| priority == output)
+                    .unwrap_or(column.priorities.len())
+            })
+            .collect()
+    }
+}
+
+fn aggregate(
+    aggregation: Aggregation,
+    mut values: impl Iterator<Item = Value>,
+) -> Result<Value, DomainError> {
+    let Some(first) = values.next() else {
+        return Ok(Value::Null);
+    };
+    values.try_fold(first, |result, value| match aggregation {
+        Aggregation::Sum => Ok((result + value)?),
+        Aggregation::Min if value.compare(&result)? == Ordering::Less => Ok(value),
+        Aggregation::Max if value.compare(&result)? == Ordering::Greater => Ok(value),
+        Aggregation::Min | Aggregation::Max => Ok(result),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn discount() -> DecisionTable {
+        DecisionTable::new("discount", HitPolicy::Unique)
+            .with_input("amount", "order.amount")
+            .with_input("country", "order.country")
+            .with_output("rate")
+            .with_rule(&["< 100", "-"], &["0"])
+            .with_rule(&["[100..500)", "\"NL\", \"BE\""], &["0.05"])
+            .with_rule(&["[100..500)", "partner_country"], &["0.02"])
+            .with_rule(&[">= 500", "-"], &["0.1"])
+    }
+
+    fn order(amount: i64, country: &str) -> HashMap<String, Value> {
+        let order = BTreeMap::from([
+            ("amount".to_string(), Value::from(amount)),
+            ("country".to_string(), Value::from(country)),
+        ]);
+        HashMap::from([("order".to_string(), Value::Map(order))])
+    }
+
+    #[test]
+    fn test_compile_to_conditions() {
+        let table = discount().compile().unwrap();
+        let conditions: Vec<String> = table.conditions().map(ToString::to_string).collect();
+        assert_eq!(
+            conditions,
+            [
+                "order.amount < 100",
+                "order.amount >= 100 and order.amount < 500 and order.country in [\"NL\", \"BE\"]",
+                "order.amount >= 100 and order.amount < 500 and order.country == partner_country",
+                "order.amount >= 500",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_evaluate_unique() {
+        let table = discount();
+        let mut variables = order(250, "BE");
+        variables.insert("partner_country".to_string(), Value::from("DE"));
+        assert_eq!(table.evaluate(&variables).unwrap().to_string(), "0.05");
+        variables.insert("partner_country".to_string(), Value::from("BE"));
+        assert!(matches!(
+            table.evaluate(&variables),
+            Err(DomainError::HitPolicyViolation { rules, .. }) if rules == [1, 2]
+        ));
+        let mut variables = order(50, "FR");
+        variables.insert("partner_country".to_string(), Value::from("FR"));
+        assert_eq!(table.evaluate(&variables).unwrap().to_string(), "0");
+    }
+
+    #[test]
+    fn test_evaluate_hit_policies() {
+        let table = |hit_policy| {
+            DecisionTable::new("shipping", hit_policy)
+                .with_input("weight", "weight")
+                .with_prioritized_output(
+                    "carrier",
+                    vec![Value::from("freight"), Value::from("parcel")],
+                )
+                .with_output("cost")
+                .with_rule(&["<= 30"], &["\"parcel\"", "10"])
+                .with_rule(&["> 20"], &["\"freight\"", "weight * 2"])
+                .with_rule(&["-"], &["\"letter\"", "1"])
+        };
+        let variables = HashMap::from([("weight".to_string(), Value::from(25_i64))]);
+        let first = table(HitPolicy::First).evaluate(&variables).unwrap();
+        assert_eq!(first.to_string(), "{carrier: parcel, cost: 10}");
+        let priority = table(HitPolicy::Priority).evaluate(&variables).unwrap();
+        assert_eq!(priority.to_string(), "{carrier: freight, cost: 50}");
+        let collected = table(HitPolicy::Collect(None))
+            .evaluate(&variables)
+            .unwrap();
+        assert_eq!(
+            collected.to_string(),
+            "[{carrier: parcel, cost: 10}, {carrier: freight, cost: 50}, {carrier: letter, cost: 1}]"
+        );
+        let surcharges = |aggregation| {
+            DecisionTable::new("surcharges", HitPolicy::Collect(Some(aggregation)))
+                .with_input("weight", "weight")
+                .with_output("surcharge")
+                .with_rule(&["> 20"], &["5"])
+                .with_rule(&["[10..30]"], &["2.5"])
+                .with_rule(&["> 100"], &["50"])
+                .evaluate(&variables)
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(surcharges(Aggregation::Sum), "7.5");
+        assert_eq!(surcharges(Aggregation::Min), "2.5");
+        assert_eq!(surcharges(Aggregation::Max), "5");
+    }
+
+    #[test]
+    fn test_check_overlaps_and_gaps() {
+        let table = DecisionTable::new("discount", HitPolicy::Unique)
+            .with_input("amount", "amount")
+            .with_input("country", "country")
+            .with_output("rate")
+            .with_rule(&["< 100", "-"], &["0"])
+            .with_rule(&["[100..500]", "\"NL\", \"BE\""], &["0.05"])
+            .with_rule(&["[100..500)", "\"BE\""], &["0.04"])
+            .with_rule(&["> 500", "-"], &["0.1"]);
+        let check = table.check().unwrap();
+        assert_eq!(check.overlaps, [(1, 2)]);
+        let gaps: Vec<Vec<String>> = [
+            "amount == 100",
+            "amount > 100 and amount < 500",
+            "amount == 500",
+        ]
+        .into_iter()
+        .flat_map(|amount| {
+            [
+                "country < \"BE\"",
+                "country > \"BE\" and country < \"NL\"",
+                "country > \"NL\"",
+            ]
+            .map(|country| vec![amount.to_string(), country.to_string()])
+        })
+        .collect();
+        assert_eq!(check.gaps, gaps);
+        let table = DecisionTable::new("rate", HitPolicy::Unique)
+            .with_input("amount", "amount")
+            .with_output("rate")
+            .with_rule(&["< limit"], &["0"]);
+        assert!(matches!(
+            table.check(),
+            Err(DomainError::InvalidDecisionTable { .. })
+        ));
+    }
+
+    #[test]
+    fn test_check_gaps_by_region() {
+        let table = DecisionTable::new("shipping", HitPolicy::First)
+            .with_input("weight", "weight")
+            .with_input("country", "country")
+            .with_input("service", "service")
+            .with_output("cost")
+            .with_rule(&["<= 30", "\"NL\"", "-"], &["5"])
+            .with_rule(&["<= 30", "-", "\"standard\""], &["15"]);
+        let check = table.check().unwrap();
+        assert_eq!(check.overlaps, [(0, 1)]);
+        assert_eq!(
+            check.gaps,
+            [
+                ["weight < 30", "country < \"NL\"", "service < \"standard\""],
+                ["weight < 30", "country < \"NL\"", "service > \"standard\""],
+                ["weight < 30", "country > \"NL\"", "service < \"standard\""],
+                ["weight < 30", "country > \"NL\"", "service > \"standard\""],
+                ["weight == 30", "country < \"NL\"", "service < \"standard\""],
+                ["weight == 30", "country < \"NL\"", "service > \"standard\""],
+                ["weight == 30", "country > \"NL\"", "service < \"standard\""],
+                ["weight == 30", "country > \"NL\"", "service > \"standard\""],
+                ["weight > 30", "-", "-"],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_gives_up_on_too_many_combinations() {
+        // Every rule tests a single column, which leaves a gap for every way to miss all of them.
+        let columns = 20;
+        let mut table = DecisionTable::new("flags", HitPolicy::First).with_output("flag");
+        for column in 0..columns {
+            let mut tests = vec!["-"; columns];
+            tests[column] = "1";
+            table = table
+                .with_input(format!("flag_{}", column), format!("flag_{}", column))
+                .with_rule(&tests, &["true"]);
+        }
+        assert!(matches!(
+            table.check(),
+            Err(DomainError::InvalidDecisionTable { .. })
+        ));
+    }
+}
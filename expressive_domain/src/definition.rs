@@ -0,0 +1,323 @@
+use crate::error::DomainError;
+use crate::module::ModuleRegistry;
+use crate::schema::InputSchema;
+use crate::test_case::{TestCase, TestReport};
+use chrono::{DateTime, Utc};
+use expressive_data::value::Value;
+use expressive_expression::ast::Expression;
+use expressive_expression::diff::{diff, Change};
+use expressive_expression::evaluate::{evaluate_expression, EvaluationOptions};
+use expressive_expression::types::{check, Type};
+use std::collections::{BTreeSet, HashMap};
+
+/// A named expression as it is stored, reviewed and referenced, e.g. the net price of an order
+/// line.
+///
+/// The id identifies the definition across its versions, the name is what people call it. A
+/// definition is only valid when its source parses and type checks against its inputs and its
+/// result fits the declared output type. Calls to the functions of the imported modules are
+/// resolved before type checking.
+///
+/// A version is effective from its `valid_from` up to but not including its `valid_to`, where a
+/// missing bound leaves the period open on that side.
+///
+/// The tests are examples that rule authors maintain along with the source, see
+/// [`ExpressionDefinition::run_tests`].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExpressionDefinition {
+    pub id: String,
+    pub name: String,
+    pub version: u32,
+    pub source: String,
+    pub inputs: InputSchema,
+    pub imports: Vec<String>,
+    pub output: Type,
+    pub description: String,
+    pub tags: BTreeSet<String>,
+    pub valid_from: Option<DateTime<Utc>>,
+    pub valid_to: Option<DateTime<Utc>>,
+    pub tests: Vec<TestCase>,
+}
+
+impl ExpressionDefinition {
+    /// A first version of a definition without declared inputs that may produce any type.
+    pub fn new(
+        id: impl Into<String>,
+        name: impl Into<String>,
+        source: impl Into<String>,
+    ) -> ExpressionDefinition {
+        ExpressionDefinition {
+            id: id.into(),
+            name: name.into(),
+            version: 1,
+            source: source.into(),
+            inputs: InputSchema::new(),
+            imports: Vec::new(),
+            output: Type::Any,
+            description: String::new(),
+            tags: BTreeSet::new(),
+            valid_from: None,
+            valid_to: None,
+            tests: Vec::new(),
+        }
+    }
+
+    pub fn with_version(mut self, version: u32) -> ExpressionDefinition {
+        self.version = version;
+        self
+    }
+
+    pub fn with_input(mut self, name: impl Into<String>, value_type: Type) -> ExpressionDefinition {
+        self.inputs = self.inputs.with_variable(name, value_type);
+        self
+    }
+
+    pub fn with_import(mut self, module: impl Into<String>) -> ExpressionDefinition {
+        self.imports.push(module.into());
+        self
+    }
+
+    pub fn with_output(mut self, output: Type) -> ExpressionDefinition {
+        self.output = output;
+        self
+    }
+
+    pub fn with_description(mut self, description: impl Into<String>) -> ExpressionDefinition {
+        self.description = description.into();
+        self
+    }
+
+    pub fn with_tag(mut self, tag: impl Into<String>) -> ExpressionDefinition {
+        self.tags.insert(tag.into());
+        self
+    }
+
+    pub fn with_valid_from(mut self, valid_from: DateTime<Utc>) -> ExpressionDefinition {
+        self.valid_from = Some(valid_from);
+        self
+    }
+
+    pub fn with_valid_to(mut self, valid_to: DateTime<Utc>) -> ExpressionDefinition {
+        self.valid_to = Some(valid_to);
+        self
+    }
+
+    pub fn with_test(mut self, test: TestCase) -> ExpressionDefinition {
+        self.tests.push(test);
+        self
+    }
+
+    pub fn is_effective_at(&self, at: DateTime<Utc>) -> bool {
+        self.valid_from.is_none_or(|valid_from| valid_from <= at)
+            && self.valid_to.is_none_or(|valid_to| at < valid_to)
+    }
+
+    /// Parses and type checks the source, returning the parsed expression.
+    pub fn validate(&self) -> Result<Expression, DomainError> {
+        self.validate_with(&ModuleRegistry::new())
+    }
+
+    /// Parses the source, resolves the imported functions and type checks the result, returning the
+    /// resolved expression.
+    pub fn validate_with(&self, modules: &ModuleRegistry) -> Result<Expression, DomainError> {
+        if let (Some(valid_from), Some(valid_to)) = (self.valid_from, self.valid_to) {
+            if valid_to <= valid_from {
+                return Err(DomainError::EmptyValidity {
+                    id: self.id.clone(),
+                    version: self.version,
+                });
+            }
+        }
+        let invalid = |source| DomainError::InvalidDefinition {
+            id: self.id.clone(),
+            source,
+        };
+        let expression: Expression = self.source.parse().map_err(invalid)?;
+        let expression = modules.resolve(&expression, &self.imports)?;
+        let actual = check(&expression, &self.inputs.types()).map_err(invalid)?;
+        if !self.output.accepts(&actual) {
+            return Err(DomainError::OutputTypeMismatch {
+                id: self.id.clone(),
+                expected: self.output.to_string(),
+                actual: actual.to_string(),
+            });
+        }
+        Ok(expression)
+    }
+
+    /// Evaluates the expression after checking the variables against the inputs.
+    pub fn evaluate(&self, variables: &HashMap<String, Value>) -> Result<Value, DomainError> {
+        self.evaluate_with(&ModuleRegistry::new(), variables)
+    }
+
+    /// Evaluates the inputs of every test and compares the results with their expectations.
+    pub fn run_tests(&self, modules: &ModuleRegistry) -> TestReport {
+        let results = self
+            .tests
+            .iter()
+            .map(|test| {
+                let inputs = test.inputs.clone().into_iter().collect();
+                test.check(self.evaluate_with(modules, &inputs))
+            })
+            .collect();
+        TestReport { results }
+    }
+
+    /// The changes to the expression from this version to another version, ignoring formatting.
+    pub fn diff(&self, other: &ExpressionDefinition) -> Result<Vec<Change>, DomainError> {
+        let parse = |definition: &ExpressionDefinition| {
+            definition.source.parse::<Expression>().map_err(|source| {
+                DomainError::InvalidDefinition {
+                    id: definition.id.clone(),
+                    source,
+                }
+            })
+        };
+        Ok(diff(&parse(self)?, &parse(other)?))
+    }
+
+    pub fn evaluate_with(
+        &self,
+        modules: &ModuleRegistry,
+        variables: &HashMap<String, Value>,
+    ) -> Result<Value, DomainError> {
+        let expression = self.validate_with(modules)?;
+        let variables = self.inputs.validate(variables)?;
+        let options = EvaluationOptions::default();
+        Ok(evaluate_expression(&expression, &variables, &options)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expressive_data::money::{Currency, Money};
+    use expressive_data::numeric::NumericValue;
+
+    fn net_price() -> ExpressionDefinition {
+        ExpressionDefinition::new(
+            "net-price",
+            "Net price",
+            "price * quantity * (1 - discount)",
+        )
+        .with_input("price", Type::Money)
+        .with_input("quantity", Type::Number)
+        .with_input("discount", Type::Number)
+        .with_output(Type::Money)
+        .with_description("The price of an order line after discount.")
+        .with_tag("pricing")
+    }
+
+    #[test]
+    fn test_validate_and_evaluate() {
+        let definition = net_price();
+        assert!(definition.validate().is_ok());
+        let variables = HashMap::from([
+            (
+                "price".to_string(),
+                Value::from(Money::new(10.into(), Currency::from_code("EUR").unwrap())),
+            ),
+            ("quantity".to_string(), Value::from(3_i64)),
+            (
+                "discount".to_string(),
+                Value::from(NumericValue::Decimal("0.1".parse().unwrap())),
+            ),
+        ]);
+        let result = definition.evaluate(&variables).unwrap();
+        assert_eq!(result.to_string(), "EUR 27.00");
+        let variables = HashMap::from([("quantity".to_string(), Value::from("3"))]);
+        assert!(matches!(
+            definition.evaluate(&variables),
+            Err(DomainError::InvalidInput { violations }) if violations.len() == 3
+        ));
+    }
+
+    #[test]
+    fn test_validate_rejects_invalid_definitions() {
+        let definition = net_price().with_output(Type::Number);
+        assert!(matches!(
+            definition.validate(),
+            Err(DomainError::OutputTypeMismatch { .. })
+        ));
+        let mut definition = net_price();
+        definition.source = "price * ".to_string();
+        assert!(matches!(
+            definition.validate(),
+            Err(DomainError::InvalidDefinition { .. })
+        ));
+        definition.source = "price * vat".to_string();
+        assert!(matches!(
+            definition.validate(),
+            Err(DomainError::InvalidDefinition { .. })
+        ));
+        definition.source = "price + quantity".to_string();
+        let error = definition.validate().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "the definition 'net-price' is invalid: type mismatch in 'price + quantity': '+' \
+             cannot be applied to a money and a number"
+        );
+    }
+
+    #[test]
+    fn test_effective_period() {
+        let date = |date: &str| date.parse::<DateTime<Utc>>().unwrap();
+        let definition = net_price()
+            .with_valid_from(date("2024-01-01T00:00:00Z"))
+            .with_valid_to(date("2025-01-01T00:00:00Z"));
+        assert!(!definition.is_effective_at(date("2023-12-31T23:59:59Z")));
+        assert!(definition.is_effective_at(date("2024-01-01T00:00:00Z")));
+        assert!(!definition.is_effective_at(date("2025-01-01T00:00:00Z")));
+        assert!(net_price().is_effective_at(date("1970-01-01T00:00:00Z")));
+        let definition = definition.with_valid_to(date("2024-01-01T00:00:00Z"));
+        assert_eq!(
+            definition.validate().unwrap_err().to_string(),
+            "version 1 of the definition 'net-price' is never effective since it ends before it \
+             starts"
+        );
+    }
+
+    #[test]
+    fn test_imported_functions() {
+        let mut modules = ModuleRegistry::new();
+        modules
+            .insert(
+                crate::module::Module::new("tax")
+                    .with_function("fn net(gross, vat) = gross / (1 + vat)"),
+            )
+            .unwrap();
+        let definition = ExpressionDefinition::new("net", "Net amount", "net(gross, 0.25)")
+            .with_import("tax")
+            .with_input("gross", Type::Money)
+            .with_output(Type::Money);
+        let gross = Value::from(Money::new(125.into(), Currency::from_code("EUR").unwrap()));
+        let variables = HashMap::from([("gross".to_string(), gross)]);
+        let result = definition.evaluate_with(&modules, &variables).unwrap();
+        assert_eq!(result.to_string(), "EUR 100.00");
+        assert!(matches!(
+            definition.validate(),
+            Err(DomainError::UndefinedModule { .. })
+        ));
+        let definition = ExpressionDefinition {
+            imports: Vec::new(),
+            ..definition
+        };
+        assert!(matches!(
+            definition.validate_with(&modules),
+            Err(DomainError::InvalidDefinition { .. })
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let definition = net_price();
+        let json = serde_json::to_string(&definition).unwrap();
+        let deserialized: ExpressionDefinition = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, definition);
+        assert!(json.contains(
+            r#""inputs":{"variables":{"discount":{"name":"discount","value_type":"number""#
+        ));
+    }
+}
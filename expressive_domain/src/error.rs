@@ -0,0 +1,78 @@
+use crate::schema::Violation;
+use chrono::{DateTime, Utc};
+use expressive_data::error::DataError;
+use expressive_expression::error::ExpressionError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum DomainError {
+    #[error("the definition '{id:}' is invalid: {source:}")]
+    InvalidDefinition { id: String, source: ExpressionError },
+    #[error("the definition '{id:}' declares output type '{expected:}' but its expression is a '{actual:}'")]
+    OutputTypeMismatch {
+        id: String,
+        expected: String,
+        actual: String,
+    },
+    #[error("the tests of the definition '{id:}' fail: {}", .failures.join("; "))]
+    FailingTests { id: String, failures: Vec<String> },
+    #[error("version {version:} of the definition '{id:}' already exists")]
+    DuplicateVersion { id: String, version: u32 },
+    #[error("version {version:} of the definition '{id:}' is never effective since it ends before it starts")]
+    EmptyValidity { id: String, version: u32 },
+    #[error("no version of the definition '{id:}' is effective at {at:}")]
+    NotEffective { id: String, at: DateTime<Utc> },
+    #[error("the definition '{id:}' is undefined")]
+    UndefinedDefinition { id: String },
+    #[error("version {version:} of the definition '{id:}' is undefined")]
+    UndefinedVersion { id: String, version: u32 },
+    #[error("'{name:}' is a formula and cannot be set as an input")]
+    NotAnInput { name: String },
+    #[error("the formulas depend on each other in a cycle: {}", .path.join(" -> "))]
+    CyclicDependency { path: Vec<String> },
+    #[error("could not evaluate '{name:}': {source:}")]
+    EvaluationFailed {
+        name: String,
+        source: ExpressionError,
+    },
+    #[error("the decision table '{name:}' is invalid: {message:}")]
+    InvalidDecisionTable { name: String, message: String },
+    #[error("more than one rule of the decision table '{name:}' matches: {rules:?}")]
+    HitPolicyViolation { name: String, rules: Vec<usize> },
+    #[error("the rule '{rule:}' is invalid: {source:}")]
+    InvalidRule {
+        rule: String,
+        source: ExpressionError,
+    },
+    #[error("the condition of the rule '{rule:}' must be a boolean instead got '{type_name:}'")]
+    NonBooleanCondition { rule: String, type_name: String },
+    #[error("the input is invalid: {}", .violations.iter().map(ToString::to_string).collect::<Vec<_>>().join("; "))]
+    InvalidInput { violations: Vec<Violation> },
+    #[error("the definition of the variable '{name:}' is invalid: {message:}")]
+    InvalidVariable { name: String, message: String },
+    #[error("the module '{name:}' is undefined")]
+    UndefinedModule { name: String },
+    #[error("the module '{module:}' is invalid: {message:}")]
+    InvalidModule { module: String, message: String },
+    #[error("a function of the module '{module:}' is invalid: {source:}")]
+    InvalidFunction {
+        module: String,
+        source: ExpressionError,
+    },
+    #[error("the function '{name:}' is defined by more than one imported module: {}", .modules.join(", "))]
+    AmbiguousFunction { name: String, modules: Vec<String> },
+    #[error("the function '{function:}' takes {expected:} arguments instead got {actual:}")]
+    WrongArgumentCount {
+        function: String,
+        expected: usize,
+        actual: usize,
+    },
+    #[error("the functions call themselves: {}", .path.join(" -> "))]
+    RecursiveFunction { path: Vec<String> },
+    #[error("inlining the function '{function:}' gives an expression of more than {max:} nodes")]
+    ExpansionTooLarge { function: String, max: usize },
+    #[error(transparent)]
+    ExpressionError(#[from] ExpressionError),
+    #[error(transparent)]
+    DataError(#[from] DataError),
+}
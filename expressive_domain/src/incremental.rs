@@ -0,0 +1,212 @@
+use crate::error::DomainError;
+use crate::workbook::Workbook;
+use expressive_data::value::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// A workbook together with the values of its formulas for a set of inputs, which recomputes only
+/// the formulas downstream of the inputs that change.
+///
+/// A changed input marks the formulas that refer to it as dirty. Recomputing evaluates the dirty
+/// formulas in dependency order, and only when the value of a formula changes are the formulas that
+/// refer to it marked dirty in turn.
+#[derive(Clone, Debug)]
+pub struct IncrementalWorkbook {
+    workbook: Workbook,
+    order: Vec<String>,
+    dependents: HashMap<String, BTreeSet<String>>,
+    variables: HashMap<String, Value>,
+    dirty: BTreeSet<String>,
+    unreported: BTreeSet<String>,
+}
+
+/// The formulas a recomputation evaluated and those whose value changed since the last successful
+/// recomputation, in evaluation order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Recomputation {
+    pub evaluated: Vec<String>,
+    pub changed: Vec<String>,
+}
+
+impl IncrementalWorkbook {
+    /// Evaluates all formulas of the workbook for the inputs.
+    pub fn new(
+        workbook: Workbook,
+        inputs: HashMap<String, Value>,
+    ) -> Result<IncrementalWorkbook, DomainError> {
+        let order: Vec<String> = workbook.order()?.into_iter().map(String::from).collect();
+        let mut dependents: HashMap<String, BTreeSet<String>> = HashMap::new();
+        for name in &order {
+            let Some(expression) = workbook.formula(name) else {
+                continue;
+            };
+            for variable in expression.variables() {
+                dependents.entry(variable).or_default().insert(name.clone());
+            }
+        }
+        let dirty = order.iter().cloned().collect();
+        let mut incremental = IncrementalWorkbook {
+            workbook,
+            order,
+            dependents,
+            variables: inputs,
+            dirty,
+            unreported: BTreeSet::new(),
+        };
+        incremental.recompute()?;
+        Ok(incremental)
+    }
+
+    /// Changes an input, marking the formulas that refer to it as dirty when its value differs. The
+    /// values of formulas are only ever computed, so setting one is an error.
+    pub fn set_input(&mut self, name: impl Into<String>, value: Value) -> Result<(), DomainError> {
+        let name = name.into();
+        if self.workbook.formula(&name).is_some() {
+            return Err(DomainError::NotAnInput { name });
+        }
+        if self.variables.get(&name) == Some(&value) {
+            return Ok(());
+        }
+        if let Some(dependents) = self.dependents.get(&name) {
+            self.dirty.extend(dependents.iter().cloned());
+        }
+        self.variables.insert(name, value);
+        Ok(())
+    }
+
+    /// Changes an input and recomputes the formulas downstream of it.
+    pub fn update(
+        &mut self,
+        name: impl Into<String>,
+        value: Value,
+    ) -> Result<Recomputation, DomainError> {
+        self.set_input(name, value)?;
+        self.recompute()
+    }
+
+    /// Evaluates the dirty formulas. When a formula fails it stays dirty, together with the
+    /// formulas that were not evaluated yet, and the formulas that already changed are reported as
+    /// changed by the next recomputation that succeeds.
+    pub fn recompute(&mut self) -> Result<Recomputation, DomainError> {
+        let mut recomputation = Recomputation::default();
+        for name in &self.order {
+            if !self.dirty.contains(name) {
+                continue;
+            }
+            let value = self.workbook.evaluate_formula(name, &self.variables)?;
+            self.dirty.remove(name);
+            recomputation.evaluated.push(name.clone());
+            if self.variables.get(name) != Some(&value) {
+                if let Some(dependents) = self.dependents.get(name) {
+                    self.dirty.extend(dependents.iter().cloned());
+                }
+                self.variables.insert(name.clone(), value);
+                self.unreported.insert(name.clone());
+            }
+        }
+        recomputation.changed = self
+            .order
+            .iter()
+            .filter(|name| self.unreported.contains(*name))
+            .cloned()
+            .collect();
+        self.unreported.clear();
+        Ok(recomputation)
+    }
+
+    /// The last computed value of a formula, or the value of an input.
+    pub fn value(&self, name: &str) -> Option<&Value> {
+        self.variables.get(name)
+    }
+
+    /// The last computed values of all formulas.
+    pub fn values(&self) -> BTreeMap<&str, &Value> {
+        self.order
+            .iter()
+            .filter_map(|name| Some((name.as_str(), self.variables.get(name)?)))
+            .collect()
+    }
+
+    pub fn is_dirty(&self, name: &str) -> bool {
+        self.dirty.contains(name)
+    }
+
+    pub fn workbook(&self) -> &Workbook {
+        &self.workbook
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn number(value: i64) -> Value {
+        Value::from(value)
+    }
+
+    fn quote() -> IncrementalWorkbook {
+        let mut workbook = Workbook::new();
+        workbook
+            .insert("subtotal", "quantity * unit_price")
+            .unwrap();
+        workbook
+            .insert("surcharge", "max(weight - 10, 0) * 2")
+            .unwrap();
+        workbook.insert("shipping", "5 + surcharge").unwrap();
+        workbook.insert("total", "subtotal + shipping").unwrap();
+        let inputs = HashMap::from([
+            ("quantity".to_string(), number(2)),
+            ("unit_price".to_string(), number(5)),
+            ("weight".to_string(), number(3)),
+        ]);
+        IncrementalWorkbook::new(workbook, inputs).unwrap()
+    }
+
+    #[test]
+    fn test_recompute_only_downstream() {
+        let mut quote = quote();
+        assert_eq!(quote.value("total"), Some(&number(15)));
+        let recomputation = quote.update("quantity", number(3)).unwrap();
+        assert_eq!(recomputation.evaluated, ["subtotal", "total"]);
+        assert_eq!(recomputation.changed, ["subtotal", "total"]);
+        assert_eq!(quote.value("total"), Some(&number(20)));
+    }
+
+    #[test]
+    fn test_unchanged_values_stop_propagation() {
+        let mut quote = quote();
+        let recomputation = quote.update("weight", number(4)).unwrap();
+        assert_eq!(recomputation.evaluated, ["surcharge"]);
+        assert!(recomputation.changed.is_empty());
+        let recomputation = quote.update("weight", number(4)).unwrap();
+        assert_eq!(recomputation, Recomputation::default());
+        let recomputation = quote.update("weight", number(12)).unwrap();
+        assert_eq!(recomputation.changed, ["surcharge", "shipping", "total"]);
+        assert_eq!(quote.value("total"), Some(&number(19)));
+    }
+
+    #[test]
+    fn test_formulas_are_not_inputs() {
+        let mut quote = quote();
+        let result = quote.set_input("total", number(0));
+        assert!(matches!(result, Err(DomainError::NotAnInput { .. })));
+        assert_eq!(quote.value("total"), Some(&number(15)));
+        assert!(!quote.is_dirty("total"));
+    }
+
+    #[test]
+    fn test_failed_formulas_stay_dirty() {
+        let mut quote = quote();
+        quote.set_input("weight", number(20)).unwrap();
+        let result = quote.update("unit_price", Value::String("five".into()));
+        assert!(matches!(result, Err(DomainError::EvaluationFailed { .. })));
+        assert!(quote.is_dirty("subtotal"));
+        assert!(!quote.is_dirty("shipping"));
+        let recomputation = quote.update("unit_price", number(6)).unwrap();
+        assert_eq!(recomputation.evaluated, ["subtotal", "total"]);
+        assert_eq!(
+            recomputation.changed,
+            ["surcharge", "shipping", "subtotal", "total"]
+        );
+        assert_eq!(quote.values()["total"], &number(37));
+    }
+}
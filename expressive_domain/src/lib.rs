@@ -0,0 +1,10 @@
+pub mod decision_table;
+pub mod definition;
+pub mod error;
+pub mod incremental;
+pub mod module;
+pub mod repository;
+pub mod rule_set;
+pub mod schema;
+pub mod test_case;
+pub mod workbook;
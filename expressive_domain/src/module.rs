@@ -0,0 +1,565 @@
+use crate::error::DomainError;
+use expressive_expression::ast::{Accessor, Expression, FunctionDefinition};
+use expressive_expression::evaluate::is_builtin_function;
+use std::collections::{BTreeMap, HashMap};
+
+/// A named group of user-defined functions, e.g. `fn net(gross, vat) = gross / (1 + vat)`, that
+/// expressions and other modules import to call them.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Module {
+    pub name: String,
+    pub imports: Vec<String>,
+    pub functions: Vec<String>,
+}
+
+impl Module {
+    pub fn new(name: impl Into<String>) -> Module {
+        Module {
+            name: name.into(),
+            imports: Vec::new(),
+            functions: Vec::new(),
+        }
+    }
+
+    pub fn with_import(mut self, module: impl Into<String>) -> Module {
+        self.imports.push(module.into());
+        self
+    }
+
+    pub fn with_function(mut self, source: impl Into<String>) -> Module {
+        self.functions.push(source.into());
+        self
+    }
+}
+
+// A module with its functions parsed, by name.
+#[derive(Clone, Debug)]
+struct ParsedModule {
+    module: Module,
+    functions: BTreeMap<String, FunctionDefinition>,
+}
+
+/// The largest number of nodes that inlining a call to a user-defined function may produce. Since
+/// an argument is copied for every use of its parameter, nested calls can grow exponentially.
+pub const MAX_RESOLVED_SIZE: usize = 10_000;
+
+/// The modules that expressions can import, by name.
+///
+/// Calls to user-defined functions are resolved at compile time: a call is replaced by the body of
+/// the function with the arguments in place of its parameters. A function sees the functions of its
+/// own module and of the modules that its module imports, where its own module takes precedence.
+/// A function that ends up calling itself is rejected, since it would never stop expanding, and so
+/// is a call that expands to more than [`MAX_RESOLVED_SIZE`] nodes.
+#[derive(Clone, Debug, Default)]
+pub struct ModuleRegistry {
+    modules: BTreeMap<String, ParsedModule>,
+}
+
+impl ModuleRegistry {
+    pub fn new() -> ModuleRegistry {
+        ModuleRegistry::default()
+    }
+
+    /// Adds a module, or replaces the module with the same name. The modules it imports must
+    /// already exist, and all of its functions must use all of their parameters and resolve, as
+    /// must the functions of the modules that import it directly or through other modules.
+    pub fn insert(&mut self, module: Module) -> Result<(), DomainError> {
+        let invalid = |message: String| DomainError::InvalidModule {
+            module: module.name.clone(),
+            message,
+        };
+        let mut functions = BTreeMap::new();
+        for source in &module.functions {
+            let function: FunctionDefinition =
+                source
+                    .parse()
+                    .map_err(|source| DomainError::InvalidFunction {
+                        module: module.name.clone(),
+                        source,
+                    })?;
+            if is_builtin_function(&function.name) {
+                return Err(invalid(format!(
+                    "the function '{}' is a built-in function",
+                    function.name
+                )));
+            }
+            if let Some(unbound) = function
+                .body
+                .variables()
+                .into_iter()
+                .find(|variable| !function.parameters.contains(variable))
+            {
+                return Err(invalid(format!(
+                    "the function '{}' refers to '{}' which is not one of its parameters",
+                    function.name, unbound
+                )));
+            }
+            // A call is inlined by substituting its arguments, so an unused argument would never be
+            // type checked or evaluated.
+            let used = function.body.variables();
+            if let Some(unused) = function
+                .parameters
+                .iter()
+                .find(|parameter| !used.contains(*parameter))
+            {
+                return Err(invalid(format!(
+                    "the function '{}' does not use its parameter '{}'",
+                    function.name, unused
+                )));
+            }
+            if functions.contains_key(&function.name) {
+                return Err(invalid(format!(
+                    "the function '{}' is defined more than once",
+                    function.name
+                )));
+            }
+            functions.insert(function.name.clone(), function);
+        }
+        if let Some(import) = module
+            .imports
+            .iter()
+            .find(|import| **import != module.name && !self.modules.contains_key(*import))
+        {
+            return Err(DomainError::UndefinedModule {
+                name: import.clone(),
+            });
+        }
+        let mut modules = self.modules.clone();
+        modules.insert(
+            module.name.clone(),
+            ParsedModule {
+                module: module.clone(),
+                functions,
+            },
+        );
+        let registry = ModuleRegistry { modules };
+        registry.check(&module.name)?;
+        for importer in registry.importers(&module.name) {
+            registry.check(importer)?;
+        }
+        *self = registry;
+        Ok(())
+    }
+
+    // Resolves every function of a module.
+    fn check(&self, name: &str) -> Result<(), DomainError> {
+        let parsed = &self.modules[name];
+        for function in parsed.functions.values() {
+            let mut path = vec![format!("{}.{}", name, function.name)];
+            self.resolve_in(
+                Some(name),
+                &parsed.module.imports,
+                &function.body,
+                &mut path,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Whether the imports, or the modules that they import in turn, include a module.
+    pub(crate) fn reaches(&self, imports: &[String], module: &str) -> bool {
+        let importers = self.importers(module);
+        imports
+            .iter()
+            .any(|import| import == module || importers.contains(&import.as_str()))
+    }
+
+    // The other modules that import a module, directly or through other modules.
+    fn importers(&self, name: &str) -> Vec<&str> {
+        let mut importers: Vec<&str> = Vec::new();
+        let mut pending = vec![name];
+        while let Some(imported) = pending.pop() {
+            for (other, parsed) in &self.modules {
+                let imports = parsed
+                    .module
+                    .imports
+                    .iter()
+                    .any(|import| import == imported);
+                if imports && other != name && !importers.contains(&other.as_str()) {
+                    importers.push(other);
+                    pending.push(other);
+                }
+            }
+        }
+        importers
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Module> {
+        self.modules.get(name).map(|parsed| &parsed.module)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.modules.keys().map(String::as_str)
+    }
+
+    pub fn function(&self, module: &str, name: &str) -> Option<&FunctionDefinition> {
+        self.modules.get(module)?.functions.get(name)
+    }
+
+    /// Replaces the calls to the functions of the imported modules by their bodies. Calls to other
+    /// functions are left for the type checker to report.
+    pub fn resolve(
+        &self,
+        expression: &Expression,
+        imports: &[String],
+    ) -> Result<Expression, DomainError> {
+        if let Some(import) = imports
+            .iter()
+            .find(|import| !self.modules.contains_key(*import))
+        {
+            return Err(DomainError::UndefinedModule {
+                name: import.clone(),
+            });
+        }
+        self.resolve_in(None, imports, expression, &mut Vec::new())
+    }
+
+    // Finds the module that defines a function in the scope of a module and its imports.
+    fn lookup<'a>(
+        &'a self,
+        module: Option<&'a str>,
+        imports: &'a [String],
+        name: &str,
+    ) -> Result<Option<(&'a str, &'a FunctionDefinition)>, DomainError> {
+        if let Some(function) = module.and_then(|module| self.function(module, name)) {
+            return Ok(module.map(|module| (module, function)));
+        }
+        let found: Vec<(&str, &FunctionDefinition)> = imports
+            .iter()
+            .filter_map(|import| Some((import.as_str(), self.function(import, name)?)))
+            .collect();
+        match found.as_slice() {
+            [] => Ok(None),
+            [found] => Ok(Some(*found)),
+            _ => Err(DomainError::AmbiguousFunction {
+                name: name.to_string(),
+                modules: found.iter().map(|(module, _)| module.to_string()).collect(),
+            }),
+        }
+    }
+
+    // Resolves an expression in the scope of a module, where the path holds the functions being
+    // expanded so that expanding one of them again is a recursion.
+    fn resolve_in(
+        &self,
+        module: Option<&str>,
+        imports: &[String],
+        expression: &Expression,
+        path: &mut Vec<String>,
+    ) -> Result<Expression, DomainError> {
+        let mut resolve =
+            |expression: &Expression| self.resolve_in(module, imports, expression, path);
+        Ok(match expression {
+            Expression::Literal { .. } | Expression::Variable { .. } => expression.clone(),
+            Expression::List { items } => Expression::List {
+                items: items.iter().map(resolve).collect::<Result<_, _>>()?,
+            },
+            Expression::Lambda { parameters, body } => Expression::Lambda {
+                parameters: parameters.clone(),
+                body: Box::new(resolve(body)?),
+            },
+            Expression::Access { target, accessor } => Expression::Access {
+                target: Box::new(resolve(target)?),
+                accessor: match accessor {
+                    Accessor::Field(name) => Accessor::Field(name.clone()),
+                    Accessor::Index(index) => Accessor::Index(Box::new(resolve(index)?)),
+                },
+            },
+            Expression::Binary {
+                operator,
+                left,
+                right,
+            } => Expression::Binary {
+                operator: *operator,
+                left: Box::new(resolve(left)?),
+                right: Box::new(resolve(right)?),
+            },
+            Expression::Prefix { operator, operand } => Expression::Prefix {
+                operator: *operator,
+                operand: Box::new(resolve(operand)?),
+            },
+            Expression::Postfix { operator, operand } => Expression::Postfix {
+                operator: *operator,
+                operand: Box::new(resolve(operand)?),
+            },
+            Expression::FunctionCall { name, arguments } => {
+                let arguments: Vec<Expression> =
+                    arguments.iter().map(resolve).collect::<Result<_, _>>()?;
+                let found = match is_builtin_function(name) {
+                    true => None,
+                    false => self.lookup(module, imports, name)?,
+                };
+                let Some((defining, function)) = found else {
+                    if let (Some(module), false) = (module, is_builtin_function(name)) {
+                        return Err(DomainError::InvalidModule {
+                            module: module.to_string(),
+                            message: format!("the function '{}' is undefined", name),
+                        });
+                    }
+                    return Ok(Expression::FunctionCall {
+                        name: name.clone(),
+                        arguments,
+                    });
+                };
+                let qualified = format!("{}.{}", defining, function.name);
+                if function.parameters.len() != arguments.len() {
+                    return Err(DomainError::WrongArgumentCount {
+                        function: qualified,
+                        expected: function.parameters.len(),
+                        actual: arguments.len(),
+                    });
+                }
+                if let Some(start) = path.iter().position(|expanding| *expanding == qualified) {
+                    let mut cycle = path[start..].to_vec();
+                    cycle.push(qualified);
+                    return Err(DomainError::RecursiveFunction { path: cycle });
+                }
+                path.push(qualified.clone());
+                let body = self.resolve_in(
+                    Some(defining),
+                    &self.modules[defining].module.imports,
+                    &function.body,
+                    path,
+                )?;
+                path.pop();
+                let bindings: HashMap<String, Expression> =
+                    function.parameters.iter().cloned().zip(arguments).collect();
+                let resolved = body.substitute(&bindings);
+                if resolved.size() > MAX_RESOLVED_SIZE {
+                    return Err(DomainError::ExpansionTooLarge {
+                        function: qualified,
+                        max: MAX_RESOLVED_SIZE,
+                    });
+                }
+                resolved
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expressive_data::value::Value;
+    use expressive_expression::evaluate::{evaluate_expression, EvaluationOptions};
+
+    fn registry() -> ModuleRegistry {
+        let mut registry = ModuleRegistry::new();
+        registry
+            .insert(
+                Module::new("tax")
+                    .with_function("fn net(gross, vat) = gross / (1 + vat)")
+                    .with_function("fn vat(net, rate) = net * rate"),
+            )
+            .unwrap();
+        registry
+            .insert(
+                Module::new("pricing")
+                    .with_import("tax")
+                    .with_function("fn discounted(price, discount) = price * (1 - discount)")
+                    .with_function(
+                        "fn net_after(price, discount) = net(discounted(price, discount), 0.25)",
+                    ),
+            )
+            .unwrap();
+        registry
+    }
+
+    fn imports(modules: &[&str]) -> Vec<String> {
+        modules.iter().map(|module| module.to_string()).collect()
+    }
+
+    #[test]
+    fn test_resolve_inlines_functions() {
+        let registry = registry();
+        let expression: Expression = "net_after(total, 0.2) + vat(1, rate)".parse().unwrap();
+        let resolved = registry
+            .resolve(&expression, &imports(&["pricing", "tax"]))
+            .unwrap();
+        assert_eq!(
+            resolved.to_string(),
+            "total * (1 - 0.2) / (1 + 0.25) + 1 * rate"
+        );
+        let variables = HashMap::from([
+            ("total".to_string(), Value::from(100_i64)),
+            ("rate".to_string(), Value::from(3_i64)),
+        ]);
+        let value = evaluate_expression(&resolved, &variables, &EvaluationOptions::default());
+        assert_eq!(value.unwrap().to_string(), "67");
+    }
+
+    #[test]
+    fn test_resolve_keeps_lambdas_apart() {
+        let mut registry = ModuleRegistry::new();
+        registry
+            .insert(Module::new("lists").with_function("fn scale(xs, x) = map(xs, y -> y * x)"))
+            .unwrap();
+        let expression: Expression = "map(rows, y -> scale(y, y))".parse().unwrap();
+        let resolved = registry.resolve(&expression, &imports(&["lists"])).unwrap();
+        assert_eq!(
+            resolved.to_string(),
+            "map(rows, y -> map(y, y_1 -> y_1 * y))"
+        );
+    }
+
+    #[test]
+    fn test_resolve_errors() {
+        let registry = registry();
+        let call = |source: &str, modules: &[&str]| {
+            let expression: Expression = source.parse().unwrap();
+            registry.resolve(&expression, &imports(modules))
+        };
+        assert!(matches!(
+            call("net(1)", &["tax"]),
+            Err(DomainError::WrongArgumentCount {
+                expected: 2,
+                actual: 1,
+                ..
+            })
+        ));
+        assert!(matches!(
+            call("net(1, 2)", &["missing"]),
+            Err(DomainError::UndefinedModule { name }) if name == "missing"
+        ));
+        // Functions that are not imported are left to the type checker.
+        assert_eq!(call("net(1, 2)", &[]).unwrap().to_string(), "net(1, 2)");
+        let mut registry = registry.clone();
+        registry
+            .insert(Module::new("other").with_function("fn net(gross, vat) = gross - vat"))
+            .unwrap();
+        let expression: Expression = "net(1, 2)".parse().unwrap();
+        assert!(matches!(
+            registry.resolve(&expression, &imports(&["tax", "other"])),
+            Err(DomainError::AmbiguousFunction { modules, .. }) if modules == ["tax", "other"]
+        ));
+    }
+
+    #[test]
+    fn test_insert_rejects_invalid_modules() {
+        let mut registry = registry();
+        let insert = |registry: &mut ModuleRegistry, module: Module| {
+            registry.insert(module).map_err(|error| error.to_string())
+        };
+        assert_eq!(
+            insert(
+                &mut registry,
+                Module::new("loop").with_function("fn fact(n) = n * fact(n - 1)")
+            ),
+            Err("the functions call themselves: loop.fact -> loop.fact".to_string())
+        );
+        assert_eq!(
+            insert(
+                &mut registry,
+                Module::new("loop")
+                    .with_function("fn even(n) = n == 0 or odd(n - 1)")
+                    .with_function("fn odd(n) = n != 0 and even(n - 1)")
+            ),
+            Err("the functions call themselves: loop.even -> loop.odd -> loop.even".to_string())
+        );
+        assert_eq!(
+            insert(
+                &mut registry,
+                Module::new("lists").with_function("fn sum(xs) = 0")
+            ),
+            Err(
+                "the module 'lists' is invalid: the function 'sum' is a built-in function"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            insert(
+                &mut registry,
+                Module::new("free").with_function("fn f(x) = x + y")
+            ),
+            Err(
+                "the module 'free' is invalid: the function 'f' refers to 'y' which is not one of \
+                 its parameters"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            insert(
+                &mut registry,
+                Module::new("constants").with_function("fn one(x) = 1")
+            ),
+            Err(
+                "the module 'constants' is invalid: the function 'one' does not use its parameter \
+                 'x'"
+                .to_string()
+            )
+        );
+        assert_eq!(
+            insert(
+                &mut registry,
+                Module::new("lists").with_function("fn first(x, xs) = map(xs, x -> x)")
+            ),
+            Err(
+                "the module 'lists' is invalid: the function 'first' does not use its parameter \
+                 'x'"
+                .to_string()
+            )
+        );
+        assert!(matches!(
+            registry.insert(Module::new("syntax").with_function("fn f(x) x")),
+            Err(DomainError::InvalidFunction { .. })
+        ));
+        assert_eq!(
+            insert(
+                &mut registry,
+                Module::new("typo").with_function("fn f(x) = nett(x, 0.2)")
+            ),
+            Err("the module 'typo' is invalid: the function 'nett' is undefined".to_string())
+        );
+        assert!(registry.get("loop").is_none());
+        assert_eq!(registry.names().collect::<Vec<_>>(), ["pricing", "tax"]);
+    }
+
+    #[test]
+    fn test_insert_checks_importers() {
+        let mut registry = registry();
+        registry
+            .insert(
+                Module::new("quote")
+                    .with_import("pricing")
+                    .with_function("fn total(price) = net_after(price, 0.1)"),
+            )
+            .unwrap();
+        let result =
+            registry.insert(Module::new("tax").with_function("fn vat(net, rate) = net * rate"));
+        assert!(matches!(
+            result,
+            Err(DomainError::InvalidModule { module, .. }) if module == "pricing"
+        ));
+        let result = registry.insert(
+            Module::new("tax")
+                .with_function("fn net(gross) = gross / 1.25")
+                .with_function("fn vat(net, rate) = net * rate"),
+        );
+        assert!(matches!(
+            result,
+            Err(DomainError::WrongArgumentCount { .. })
+        ));
+        assert_eq!(
+            registry.function("tax", "net").unwrap().to_string(),
+            "fn net(gross, vat) = gross / (1 + vat)"
+        );
+    }
+
+    #[test]
+    fn test_resolve_limits_the_expanded_size() {
+        let mut registry = ModuleRegistry::new();
+        registry
+            .insert(Module::new("math").with_function("fn sq(x) = x * x"))
+            .unwrap();
+        let source = format!("{}y{}", "sq(".repeat(20), ")".repeat(20));
+        let expression: Expression = source.parse().unwrap();
+        assert!(matches!(
+            registry.resolve(&expression, &imports(&["math"])),
+            Err(DomainError::ExpansionTooLarge { function, .. }) if function == "math.sq"
+        ));
+        let expression: Expression = "sq(sq(y))".parse().unwrap();
+        let resolved = registry.resolve(&expression, &imports(&["math"])).unwrap();
+        assert_eq!(resolved.to_string(), "y * y * (y * y)");
+    }
+}
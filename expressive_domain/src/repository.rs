@@ -0,0 +1,430 @@
+use crate::definition::ExpressionDefinition;
+use crate::error::DomainError;
+use crate::module::{Module, ModuleRegistry};
+use chrono::{DateTime, Utc};
+use expressive_data::value::Value;
+use expressive_expression::diff::Change;
+use std::collections::{BTreeMap, HashMap};
+
+/// Keeps every saved version of the expression definitions, by id.
+///
+/// Only valid definitions whose tests pass are saved and a saved version never changes: a change is
+/// saved as a new version. Definitions are validated against the modules of the repository, so a
+/// module must be saved before the definitions that import it.
+///
+/// When the periods in which versions are effective overlap, the latest version takes precedence,
+/// so that a correction of a rule is saved as a new version over the same period.
+///
+/// Saving a module keeps the modules as they were before as a revision. A version of a definition
+/// evaluates against the revision it was saved with, except for the latest version, which moves on
+/// to the new revision when a module it imports changes. Evaluating as of a date thus uses the
+/// functions that were in effect for the version effective at that date.
+#[derive(Clone, Debug)]
+pub struct Repository {
+    definitions: HashMap<String, BTreeMap<u32, SavedDefinition>>,
+    revisions: Vec<ModuleRegistry>,
+}
+
+// A version of a definition with the revision of the modules that its imports resolve against.
+#[derive(Clone, Debug)]
+struct SavedDefinition {
+    definition: ExpressionDefinition,
+    revision: usize,
+}
+
+impl Default for Repository {
+    fn default() -> Self {
+        Repository {
+            definitions: HashMap::new(),
+            revisions: vec![ModuleRegistry::new()],
+        }
+    }
+}
+
+impl Repository {
+    pub fn new() -> Repository {
+        Repository::default()
+    }
+
+    pub fn save(&mut self, definition: ExpressionDefinition) -> Result<(), DomainError> {
+        Repository::check(&definition, self.modules())?;
+        let revision = self.revisions.len() - 1;
+        let versions = self.definitions.entry(definition.id.clone()).or_default();
+        if versions.contains_key(&definition.version) {
+            return Err(DomainError::DuplicateVersion {
+                id: definition.id,
+                version: definition.version,
+            });
+        }
+        versions.insert(
+            definition.version,
+            SavedDefinition {
+                definition,
+                revision,
+            },
+        );
+        Ok(())
+    }
+
+    /// Adds a module, or replaces the module with the same name, as a new revision of the modules.
+    /// The latest versions of the definitions that import the module, directly or through other
+    /// modules, must still be valid and pass their tests with it.
+    pub fn save_module(&mut self, module: Module) -> Result<(), DomainError> {
+        let mut modules = self.modules().clone();
+        let name = module.name.clone();
+        modules.insert(module)?;
+        let mut importers = Vec::new();
+        for versions in self.definitions.values() {
+            let Some(latest) = versions.values().next_back() else {
+                continue;
+            };
+            if !modules.reaches(&latest.definition.imports, &name) {
+                continue;
+            }
+            Repository::check(&latest.definition, &modules)?;
+            importers.push((latest.definition.id.clone(), latest.definition.version));
+        }
+        self.revisions.push(modules);
+        let revision = self.revisions.len() - 1;
+        for (id, version) in importers {
+            if let Some(saved) = self
+                .definitions
+                .get_mut(&id)
+                .and_then(|versions| versions.get_mut(&version))
+            {
+                saved.revision = revision;
+            }
+        }
+        Ok(())
+    }
+
+    // Validates a definition and runs its tests against the modules.
+    fn check(
+        definition: &ExpressionDefinition,
+        modules: &ModuleRegistry,
+    ) -> Result<(), DomainError> {
+        definition.validate_with(modules)?;
+        let report = definition.run_tests(modules);
+        if !report.passed() {
+            return Err(DomainError::FailingTests {
+                id: definition.id.clone(),
+                failures: report.failures().map(ToString::to_string).collect(),
+            });
+        }
+        Ok(())
+    }
+
+    /// The current modules.
+    pub fn modules(&self) -> &ModuleRegistry {
+        self.revisions
+            .last()
+            .expect("a repository has at least one revision of the modules")
+    }
+
+    /// The latest version of a definition.
+    pub fn get(&self, id: &str) -> Option<&ExpressionDefinition> {
+        self.definitions
+            .get(id)
+            .and_then(|versions| versions.values().next_back())
+            .map(|saved| &saved.definition)
+    }
+
+    pub fn get_version(&self, id: &str, version: u32) -> Option<&ExpressionDefinition> {
+        self.definitions
+            .get(id)
+            .and_then(|versions| versions.get(&version))
+            .map(|saved| &saved.definition)
+    }
+
+    /// The latest version of a definition that is effective at a point in time.
+    pub fn effective_at(&self, id: &str, at: DateTime<Utc>) -> Option<&ExpressionDefinition> {
+        self.saved_at(id, at).map(|saved| &saved.definition)
+    }
+
+    fn saved_at(&self, id: &str, at: DateTime<Utc>) -> Option<&SavedDefinition> {
+        self.definitions
+            .get(id)?
+            .values()
+            .rev()
+            .find(|saved| saved.definition.is_effective_at(at))
+    }
+
+    /// Evaluates a definition as it was effective at a point in time, e.g. the date of an invoice,
+    /// with the modules that version was saved with.
+    pub fn evaluate_at(
+        &self,
+        id: &str,
+        at: DateTime<Utc>,
+        variables: &HashMap<String, Value>,
+    ) -> Result<Value, DomainError> {
+        if !self.definitions.contains_key(id) {
+            return Err(DomainError::UndefinedDefinition { id: id.to_string() });
+        }
+        let saved = self
+            .saved_at(id, at)
+            .ok_or_else(|| DomainError::NotEffective {
+                id: id.to_string(),
+                at,
+            })?;
+        saved
+            .definition
+            .evaluate_with(&self.revisions[saved.revision], variables)
+    }
+
+    /// The changes to the expression of a definition from one version to another.
+    pub fn diff(&self, id: &str, before: u32, after: u32) -> Result<Vec<Change>, DomainError> {
+        let version = |version: u32| {
+            self.get_version(id, version)
+                .ok_or_else(|| DomainError::UndefinedVersion {
+                    id: id.to_string(),
+                    version,
+                })
+        };
+        version(before)?.diff(version(after)?)
+    }
+
+    /// All versions of a definition, oldest first.
+    pub fn versions(&self, id: &str) -> impl Iterator<Item = &ExpressionDefinition> {
+        self.definitions
+            .get(id)
+            .into_iter()
+            .flat_map(|versions| versions.values())
+            .map(|saved| &saved.definition)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_case::{Expectation, TestCase};
+    use expressive_expression::types::Type;
+
+    #[test]
+    fn test_save_keeps_versions() {
+        let mut repository = Repository::new();
+        let first = ExpressionDefinition::new("vat", "VAT rate", "0.21").with_output(Type::Number);
+        let second = first.clone().with_version(2);
+        let second = ExpressionDefinition {
+            source: "0.19".to_string(),
+            ..second
+        };
+        repository.save(second).unwrap();
+        repository.save(first.clone()).unwrap();
+        assert_eq!(repository.get("vat").unwrap().source, "0.19");
+        assert_eq!(repository.get_version("vat", 1), Some(&first));
+        assert_eq!(repository.versions("vat").count(), 2);
+        assert!(repository.get("missing").is_none());
+        assert!(matches!(
+            repository.save(first),
+            Err(DomainError::DuplicateVersion { version: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_evaluate_at() {
+        let date = |date: &str| date.parse::<DateTime<Utc>>().unwrap();
+        let mut repository = Repository::new();
+        let vat = |version: u32, rate: &str| {
+            ExpressionDefinition::new("vat", "VAT", format!("net * {}", rate))
+                .with_version(version)
+                .with_input("net", Type::Number)
+        };
+        repository
+            .save(vat(1, "0.19").with_valid_to(date("2024-01-01T00:00:00Z")))
+            .unwrap();
+        repository
+            .save(vat(2, "0.21").with_valid_from(date("2024-01-01T00:00:00Z")))
+            .unwrap();
+        // A correction of the rate for the first half of 2024.
+        repository
+            .save(
+                vat(3, "0.20")
+                    .with_valid_from(date("2024-01-01T00:00:00Z"))
+                    .with_valid_to(date("2024-07-01T00:00:00Z")),
+            )
+            .unwrap();
+        let variables = HashMap::from([("net".to_string(), Value::from(100_i64))]);
+        let evaluate = |at: &str| {
+            repository
+                .evaluate_at("vat", date(at), &variables)
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(evaluate("2023-12-31T23:59:59Z"), "19.00");
+        assert_eq!(evaluate("2024-03-15T00:00:00Z"), "20.00");
+        assert_eq!(evaluate("2024-07-01T00:00:00Z"), "21.00");
+        assert_eq!(
+            repository
+                .effective_at("vat", date("2030-01-01T00:00:00Z"))
+                .map(|definition| definition.version),
+            Some(2)
+        );
+        assert!(matches!(
+            repository.evaluate_at("missing", date("2024-01-01T00:00:00Z"), &variables),
+            Err(DomainError::UndefinedDefinition { .. })
+        ));
+        let mut repository = Repository::new();
+        repository
+            .save(vat(1, "0.19").with_valid_from(date("2024-01-01T00:00:00Z")))
+            .unwrap();
+        assert!(matches!(
+            repository.evaluate_at("vat", date("2023-01-01T00:00:00Z"), &variables),
+            Err(DomainError::NotEffective { .. })
+        ));
+    }
+
+    #[test]
+    fn test_diff_versions() {
+        let mut repository = Repository::new();
+        let discount = |version: u32, source: &str| {
+            ExpressionDefinition::new("discount", "Discount", source)
+                .with_version(version)
+                .with_input("age", Type::Number)
+                .with_input("member", Type::Boolean)
+        };
+        repository.save(discount(1, "age>=65 or member")).unwrap();
+        repository
+            .save(discount(2, "(age >= 67 and member) or member"))
+            .unwrap();
+        let changes: Vec<String> = repository
+            .diff("discount", 1, 2)
+            .unwrap()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            changes,
+            ["changed the constant 65 to 67", "added 'and member'"]
+        );
+        assert!(matches!(
+            repository.diff("discount", 1, 3),
+            Err(DomainError::UndefinedVersion { version: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn test_save_resolves_imports() {
+        let mut repository = Repository::new();
+        let definition = ExpressionDefinition::new("total", "Total", "with_vat(100)")
+            .with_import("tax")
+            .with_output(Type::Number);
+        assert!(matches!(
+            repository.save(definition.clone()),
+            Err(DomainError::UndefinedModule { .. })
+        ));
+        repository
+            .save_module(Module::new("tax").with_function("fn with_vat(net) = net * 1.21"))
+            .unwrap();
+        repository.save(definition).unwrap();
+        assert!(repository.modules().function("tax", "with_vat").is_some());
+    }
+
+    #[test]
+    fn test_evaluate_at_uses_the_modules_of_the_version() {
+        let date = |date: &str| date.parse::<DateTime<Utc>>().unwrap();
+        let tax = |rate: &str| {
+            Module::new("tax").with_function(format!("fn with_vat(net) = net * {}", rate))
+        };
+        let total = |version: u32| {
+            ExpressionDefinition::new("total", "Total", "with_vat(net)")
+                .with_version(version)
+                .with_import("tax")
+                .with_input("net", Type::Number)
+        };
+        let mut repository = Repository::new();
+        repository.save_module(tax("1.19")).unwrap();
+        repository
+            .save(total(1).with_valid_to(date("2024-01-01T00:00:00Z")))
+            .unwrap();
+        repository
+            .save(total(2).with_valid_from(date("2024-01-01T00:00:00Z")))
+            .unwrap();
+        repository.save_module(tax("1.21")).unwrap();
+        let variables = HashMap::from([("net".to_string(), Value::from(100_i64))]);
+        let evaluate = |at: &str| {
+            repository
+                .evaluate_at("total", date(at), &variables)
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(evaluate("2023-06-01T00:00:00Z"), "119.00");
+        assert_eq!(evaluate("2024-06-01T00:00:00Z"), "121.00");
+    }
+
+    #[test]
+    fn test_save_module_checks_importing_definitions() {
+        let mut repository = Repository::new();
+        repository
+            .save_module(Module::new("tax").with_function("fn with_vat(net) = net * 1.21"))
+            .unwrap();
+        repository
+            .save_module(
+                Module::new("pricing")
+                    .with_import("tax")
+                    .with_function("fn gross(net) = with_vat(net)"),
+            )
+            .unwrap();
+        let total = ExpressionDefinition::new("total", "Total", "gross(net)")
+            .with_import("pricing")
+            .with_input("net", Type::Number)
+            .with_test(
+                TestCase::new("standard rate", Expectation::Value(Value::from(121_i64)))
+                    .with_input("net", Value::from(100_i64)),
+            );
+        repository.save(total).unwrap();
+        let result = repository
+            .save_module(Module::new("tax").with_function("fn with_vat(net) = net * 1.19"));
+        assert!(matches!(
+            result,
+            Err(DomainError::FailingTests { id, .. }) if id == "total"
+        ));
+        let result = repository
+            .save_module(Module::new("tax").with_function("fn with_vat(net) = net and true"));
+        assert!(matches!(
+            result,
+            Err(DomainError::InvalidDefinition { id, .. }) if id == "total"
+        ));
+        assert_eq!(
+            repository
+                .modules()
+                .function("tax", "with_vat")
+                .unwrap()
+                .to_string(),
+            "fn with_vat(net) = net * 1.21"
+        );
+        repository
+            .save_module(Module::new("tax").with_function("fn with_vat(net) = net + net * 0.21"))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_save_rejects_failing_tests() {
+        let mut repository = Repository::new();
+        let definition = ExpressionDefinition::new("vat", "VAT", "net * 0.21")
+            .with_input("net", Type::Number)
+            .with_test(
+                TestCase::new("standard rate", Expectation::Value(Value::from(21_i64)))
+                    .with_input("net", Value::from(100_i64)),
+            );
+        repository.save(definition.clone()).unwrap();
+        let definition = ExpressionDefinition {
+            source: "net * 0.19".to_string(),
+            ..definition.with_version(2)
+        };
+        assert_eq!(
+            repository.save(definition).unwrap_err().to_string(),
+            "the tests of the definition 'vat' fail: 'standard rate' failed: result is 19.00 \
+             instead of 21"
+        );
+        assert_eq!(repository.versions("vat").count(), 1);
+    }
+
+    #[test]
+    fn test_save_rejects_invalid_definitions() {
+        let mut repository = Repository::new();
+        let definition =
+            ExpressionDefinition::new("vat", "VAT rate", "\"21%\"").with_output(Type::Number);
+        assert!(repository.save(definition).is_err());
+        assert!(repository.get("vat").is_none());
+    }
+}
@@ -0,0 +1,288 @@
+use crate::error::DomainError;
+use expressive_data::value::Value;
+use expressive_expression::ast::Expression;
+use expressive_expression::evaluate::{evaluate_expression, EvaluationOptions};
+use std::collections::{BTreeMap, HashMap};
+
+/// Whether a rule set stops at the first rule that fires or fires every rule whose condition holds.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Mode {
+    #[default]
+    FirstMatch,
+    AllMatch,
+}
+
+/// Assigns the value of an expression to a variable when a rule fires.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Assignment {
+    pub variable: String,
+    pub expression: String,
+}
+
+/// A rule fires when its condition is true, and then makes its assignments in order.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rule {
+    pub name: String,
+    pub salience: i32,
+    pub when: String,
+    pub then: Vec<Assignment>,
+}
+
+/// Rules that are tried from the highest to the lowest salience, and in the order they were added
+/// when their salience is the same.
+///
+/// A rule sees the assignments of the rules that fired before it, so with all-match rules can build
+/// on each other. The variables that rules assign are null until a rule assigns them, and a
+/// condition that is null does not hold.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RuleSet {
+    pub name: String,
+    pub mode: Mode,
+    pub rules: Vec<Rule>,
+}
+
+/// A rule that was tried while executing a rule set, with the values it assigned when it fired.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Firing {
+    pub rule: String,
+    pub fired: bool,
+    pub assignments: BTreeMap<String, Value>,
+}
+
+/// The values that the rules assigned, and a trace of the rules that were tried in order.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Execution {
+    pub assignments: BTreeMap<String, Value>,
+    pub trace: Vec<Firing>,
+}
+
+impl Execution {
+    /// The names of the rules that fired, in the order they fired.
+    pub fn fired(&self) -> impl Iterator<Item = &str> {
+        self.trace
+            .iter()
+            .filter(|firing| firing.fired)
+            .map(|firing| firing.rule.as_str())
+    }
+}
+
+impl Rule {
+    pub fn new(name: impl Into<String>, when: impl Into<String>) -> Rule {
+        Rule {
+            name: name.into(),
+            salience: 0,
+            when: when.into(),
+            then: Vec::new(),
+        }
+    }
+
+    pub fn with_salience(mut self, salience: i32) -> Rule {
+        self.salience = salience;
+        self
+    }
+
+    pub fn then(mut self, variable: impl Into<String>, expression: impl Into<String>) -> Rule {
+        self.then.push(Assignment {
+            variable: variable.into(),
+            expression: expression.into(),
+        });
+        self
+    }
+}
+
+// A rule with its condition and assignments parsed.
+struct ParsedRule<'a> {
+    rule: &'a Rule,
+    condition: Expression,
+    assignments: Vec<(&'a str, Expression)>,
+}
+
+impl RuleSet {
+    pub fn new(name: impl Into<String>, mode: Mode) -> RuleSet {
+        RuleSet {
+            name: name.into(),
+            mode,
+            rules: Vec::new(),
+        }
+    }
+
+    pub fn with_rule(mut self, rule: Rule) -> RuleSet {
+        self.rules.push(rule);
+        self
+    }
+
+    // Parses all rules up front, ordered by salience, so that a mistake in any rule is reported
+    // whether or not it would be tried.
+    fn parse(&self) -> Result<Vec<ParsedRule<'_>>, DomainError> {
+        let mut rules = self
+            .rules
+            .iter()
+            .map(|rule| {
+                let invalid = |source| DomainError::InvalidRule {
+                    rule: rule.name.clone(),
+                    source,
+                };
+                let condition = rule.when.parse().map_err(invalid)?;
+                let assignments = rule
+                    .then
+                    .iter()
+                    .map(|assignment| {
+                        let expression = assignment.expression.parse().map_err(invalid)?;
+                        Ok((assignment.variable.as_str(), expression))
+                    })
+                    .collect::<Result<_, DomainError>>()?;
+                Ok(ParsedRule {
+                    rule,
+                    condition,
+                    assignments,
+                })
+            })
+            .collect::<Result<Vec<ParsedRule>, DomainError>>()?;
+        rules.sort_by_key(|parsed| std::cmp::Reverse(parsed.rule.salience));
+        Ok(rules)
+    }
+
+    pub fn validate(&self) -> Result<(), DomainError> {
+        self.parse().map(|_| ())
+    }
+
+    pub fn execute(&self, variables: &HashMap<String, Value>) -> Result<Execution, DomainError> {
+        let rules = self.parse()?;
+        let options = EvaluationOptions::default();
+        let mut variables = variables.clone();
+        for parsed in &rules {
+            for (variable, _) in &parsed.assignments {
+                variables.entry(variable.to_string()).or_insert(Value::Null);
+            }
+        }
+        let mut execution = Execution::default();
+        for parsed in rules {
+            let name = &parsed.rule.name;
+            let failed = |source| DomainError::EvaluationFailed {
+                name: name.clone(),
+                source,
+            };
+            let fired = match evaluate_expression(&parsed.condition, &variables, &options)
+                .map_err(failed)?
+            {
+                Value::Boolean(holds) => holds,
+                Value::Null => false,
+                value => {
+                    return Err(DomainError::NonBooleanCondition {
+                        rule: name.clone(),
+                        type_name: value.type_name().to_string(),
+                    })
+                }
+            };
+            let mut assignments = BTreeMap::new();
+            if fired {
+                for (variable, expression) in &parsed.assignments {
+                    let value =
+                        evaluate_expression(expression, &variables, &options).map_err(failed)?;
+                    variables.insert(variable.to_string(), value.clone());
+                    execution
+                        .assignments
+                        .insert(variable.to_string(), value.clone());
+                    assignments.insert(variable.to_string(), value);
+                }
+            }
+            execution.trace.push(Firing {
+                rule: name.clone(),
+                fired,
+                assignments,
+            });
+            if fired && self.mode == Mode::FirstMatch {
+                break;
+            }
+        }
+        Ok(execution)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eligibility(mode: Mode) -> RuleSet {
+        RuleSet::new("eligibility", mode)
+            .with_rule(
+                Rule::new("adult", "age >= 18")
+                    .then("eligible", "true")
+                    .then("discount", "0"),
+            )
+            .with_rule(
+                Rule::new("senior", "age >= 65")
+                    .with_salience(10)
+                    .then("eligible", "true")
+                    .then("discount", "0.2"),
+            )
+            .with_rule(
+                Rule::new("loyal", "years > 5 and eligible").then("discount", "discount + 0.05"),
+            )
+            .with_rule(Rule::new("minor", "age < 18").then("eligible", "false"))
+    }
+
+    fn person(age: i64) -> HashMap<String, Value> {
+        HashMap::from([
+            ("age".to_string(), Value::from(age)),
+            ("years".to_string(), Value::from(10_i64)),
+        ])
+    }
+
+    #[test]
+    fn test_first_match_follows_salience() {
+        let execution = eligibility(Mode::FirstMatch).execute(&person(70)).unwrap();
+        assert_eq!(execution.fired().collect::<Vec<_>>(), ["senior"]);
+        assert_eq!(execution.assignments["discount"].to_string(), "0.2");
+        let execution = eligibility(Mode::FirstMatch).execute(&person(12)).unwrap();
+        let tried: Vec<(&str, bool)> = execution
+            .trace
+            .iter()
+            .map(|firing| (firing.rule.as_str(), firing.fired))
+            .collect();
+        assert_eq!(
+            tried,
+            [
+                ("senior", false),
+                ("adult", false),
+                ("loyal", false),
+                ("minor", true)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_all_match_builds_on_earlier_rules() {
+        let execution = eligibility(Mode::AllMatch).execute(&person(70)).unwrap();
+        assert_eq!(
+            execution.fired().collect::<Vec<_>>(),
+            ["senior", "adult", "loyal"]
+        );
+        assert_eq!(execution.assignments["discount"].to_string(), "0.05");
+        assert_eq!(
+            execution.trace[2].assignments["discount"].to_string(),
+            "0.05"
+        );
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        let rules = RuleSet::new("broken", Mode::AllMatch)
+            .with_rule(Rule::new("ok", "true"))
+            .with_rule(Rule::new("syntax", "age >").then("x", "1"));
+        assert!(matches!(
+            rules.validate(),
+            Err(DomainError::InvalidRule { rule, .. }) if rule == "syntax"
+        ));
+        let rules = RuleSet::new("numbers", Mode::AllMatch).with_rule(Rule::new("count", "age"));
+        assert!(matches!(
+            rules.execute(&person(1)),
+            Err(DomainError::NonBooleanCondition { .. })
+        ));
+    }
+}
@@ -0,0 +1,371 @@
+use crate::error::DomainError;
+use expressive_data::value::Value;
+use expressive_expression::types::Type;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use thiserror::Error;
+
+/// The values a variable may have besides its type.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Constraint {
+    /// Between an optional minimum and maximum, both included.
+    Range {
+        min: Option<Box<Value>>,
+        max: Option<Box<Value>>,
+    },
+    OneOf(Vec<Value>),
+}
+
+/// A variable that an expression expects. A required variable must have a value that is not null,
+/// an optional variable that is missing gets its default, or null without one.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VariableDefinition {
+    pub name: String,
+    pub value_type: Type,
+    pub required: bool,
+    pub default: Option<Value>,
+    pub constraint: Option<Constraint>,
+    pub description: String,
+}
+
+/// A way in which a variable does not match its definition.
+#[derive(Clone, Debug, PartialEq, Error)]
+pub enum Violation {
+    #[error("the variable '{name:}' is required")]
+    Missing { name: String },
+    #[error("the variable '{name:}' must be a '{expected:}' instead got '{actual:}'")]
+    WrongType {
+        name: String,
+        expected: Type,
+        actual: Type,
+    },
+    #[error("the variable '{name:}' is {value:} which is outside of its allowed range")]
+    OutOfRange { name: String, value: Value },
+    #[error("the variable '{name:}' is {value:} which is not one of its allowed values")]
+    NotAllowed { name: String, value: Value },
+    #[error(
+        "the variable '{name:}' is {value:} which cannot be compared with its range: {message:}"
+    )]
+    Incomparable {
+        name: String,
+        value: Value,
+        message: String,
+    },
+}
+
+/// The variables an expression expects.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InputSchema {
+    variables: BTreeMap<String, VariableDefinition>,
+}
+
+impl VariableDefinition {
+    /// A required variable without constraints.
+    pub fn new(name: impl Into<String>, value_type: Type) -> VariableDefinition {
+        VariableDefinition {
+            name: name.into(),
+            value_type,
+            required: true,
+            default: None,
+            constraint: None,
+            description: String::new(),
+        }
+    }
+
+    pub fn optional(mut self) -> VariableDefinition {
+        self.required = false;
+        self
+    }
+
+    /// Makes the variable optional with a default value.
+    pub fn with_default(mut self, default: Value) -> VariableDefinition {
+        self.required = false;
+        self.default = Some(default);
+        self
+    }
+
+    pub fn with_range(mut self, min: Option<Value>, max: Option<Value>) -> VariableDefinition {
+        self.constraint = Some(Constraint::Range {
+            min: min.map(Box::new),
+            max: max.map(Box::new),
+        });
+        self
+    }
+
+    pub fn with_allowed_values(mut self, values: Vec<Value>) -> VariableDefinition {
+        self.constraint = Some(Constraint::OneOf(values));
+        self
+    }
+
+    pub fn with_description(mut self, description: impl Into<String>) -> VariableDefinition {
+        self.description = description.into();
+        self
+    }
+
+    // Checks a value that is not null against the type and constraint.
+    fn check(&self, value: &Value) -> Option<Violation> {
+        let actual = Type::of(value);
+        if !self.value_type.accepts(&actual) {
+            return Some(Violation::WrongType {
+                name: self.name.clone(),
+                expected: self.value_type.clone(),
+                actual,
+            });
+        }
+        match &self.constraint {
+            Some(Constraint::Range { min, max }) => {
+                let beyond = |bound: &Option<Box<Value>>, side: Ordering| match bound {
+                    Some(bound) => value.compare(bound).map(|ordering| ordering == side),
+                    None => Ok(false),
+                };
+                match beyond(min, Ordering::Less)
+                    .and_then(|below| Ok(below || beyond(max, Ordering::Greater)?))
+                {
+                    Ok(false) => None,
+                    Ok(true) => Some(Violation::OutOfRange {
+                        name: self.name.clone(),
+                        value: value.clone(),
+                    }),
+                    Err(error) => Some(Violation::Incomparable {
+                        name: self.name.clone(),
+                        value: value.clone(),
+                        message: error.to_string(),
+                    }),
+                }
+            }
+            Some(Constraint::OneOf(values)) if !values.contains(value) => {
+                Some(Violation::NotAllowed {
+                    name: self.name.clone(),
+                    value: value.clone(),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    // Checks that the default and the values of the constraint are of the type of the variable.
+    fn check_definition(&self) -> Result<(), DomainError> {
+        let bounds = match &self.constraint {
+            Some(Constraint::Range { min, max }) => [min, max]
+                .into_iter()
+                .flatten()
+                .map(|bound| ("bound", bound.as_ref()))
+                .collect(),
+            Some(Constraint::OneOf(values)) => values
+                .iter()
+                .map(|value| ("allowed value", value))
+                .collect(),
+            None => Vec::new(),
+        };
+        let defaults = self.default.iter().map(|default| ("default", default));
+        for (role, value) in defaults.chain(bounds) {
+            let actual = Type::of(value);
+            if !self.value_type.accepts(&actual) {
+                return Err(DomainError::InvalidVariable {
+                    name: self.name.clone(),
+                    message: format!(
+                        "the {role:} {value:} is a '{actual:}' instead of a '{}'",
+                        self.value_type
+                    ),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl InputSchema {
+    pub fn new() -> InputSchema {
+        InputSchema::default()
+    }
+
+    /// Adds a required variable without constraints.
+    pub fn with_variable(mut self, name: impl Into<String>, value_type: Type) -> InputSchema {
+        let definition = VariableDefinition::new(name, value_type);
+        self.variables.insert(definition.name.clone(), definition);
+        self
+    }
+
+    /// Adds a variable, rejecting a default or constraint of another type than the variable.
+    pub fn with_definition(
+        mut self,
+        definition: VariableDefinition,
+    ) -> Result<InputSchema, DomainError> {
+        definition.check_definition()?;
+        self.variables.insert(definition.name.clone(), definition);
+        Ok(self)
+    }
+
+    pub fn variables(&self) -> impl Iterator<Item = &VariableDefinition> {
+        self.variables.values()
+    }
+
+    pub fn variable(&self, name: &str) -> Option<&VariableDefinition> {
+        self.variables.get(name)
+    }
+
+    /// The variable types in the form the type checker takes them.
+    pub fn types(&self) -> HashMap<String, Type> {
+        self.variables
+            .values()
+            .map(|definition| (definition.name.clone(), definition.value_type.clone()))
+            .collect()
+    }
+
+    /// Checks the variables against the schema, returning every violation at once. Valid variables
+    /// are returned with the defaults of the missing optional variables filled in, where variables
+    /// the schema does not declare are passed along unchecked.
+    pub fn validate(
+        &self,
+        variables: &HashMap<String, Value>,
+    ) -> Result<HashMap<String, Value>, DomainError> {
+        let mut validated = variables.clone();
+        let mut violations = Vec::new();
+        for definition in self.variables.values() {
+            match variables.get(&definition.name) {
+                Some(value) if !value.is_null() => violations.extend(definition.check(value)),
+                _ if definition.required => violations.push(Violation::Missing {
+                    name: definition.name.clone(),
+                }),
+                _ => {
+                    let default = definition.default.clone().unwrap_or(Value::Null);
+                    validated.insert(definition.name.clone(), default);
+                }
+            }
+        }
+        match violations.is_empty() {
+            true => Ok(validated),
+            false => Err(DomainError::InvalidInput { violations }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expressive_data::money::{Currency, Money};
+
+    fn schema() -> InputSchema {
+        InputSchema::new()
+            .with_definition(
+                VariableDefinition::new("quantity", Type::Number)
+                    .with_range(Some(Value::from(1_i64)), Some(Value::from(100_i64)))
+                    .with_description("The number of items ordered."),
+            )
+            .and_then(|schema| {
+                schema.with_definition(
+                    VariableDefinition::new("country", Type::String)
+                        .with_allowed_values(vec![Value::from("NL"), Value::from("BE")]),
+                )
+            })
+            .and_then(|schema| {
+                schema.with_definition(
+                    VariableDefinition::new("express", Type::Boolean)
+                        .with_default(Value::Boolean(false)),
+                )
+            })
+            .and_then(|schema| {
+                schema.with_definition(VariableDefinition::new("coupon", Type::String).optional())
+            })
+            .unwrap()
+            .with_variable("customer", Type::Map)
+    }
+
+    fn money(amount: i64, currency: &str) -> Value {
+        Value::from(Money::new(
+            amount.into(),
+            Currency::from_code(currency).unwrap(),
+        ))
+    }
+
+    #[test]
+    fn test_validate_fills_in_defaults() {
+        let variables = HashMap::from([
+            ("quantity".to_string(), Value::from(3_i64)),
+            ("country".to_string(), Value::from("NL")),
+            ("customer".to_string(), Value::Map(BTreeMap::new())),
+            ("note".to_string(), Value::from("unchecked")),
+        ]);
+        let validated = schema().validate(&variables).unwrap();
+        assert_eq!(validated["express"], Value::Boolean(false));
+        assert_eq!(validated["coupon"], Value::Null);
+        assert_eq!(validated["note"], Value::from("unchecked"));
+    }
+
+    #[test]
+    fn test_validate_reports_all_violations() {
+        let variables = HashMap::from([
+            ("quantity".to_string(), Value::from(0_i64)),
+            ("country".to_string(), Value::from("DE")),
+            ("express".to_string(), Value::from("yes")),
+            ("customer".to_string(), Value::Null),
+        ]);
+        let Err(DomainError::InvalidInput { violations }) = schema().validate(&variables) else {
+            panic!("expected the input to be invalid");
+        };
+        let messages: Vec<String> = violations.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            [
+                "the variable 'country' is DE which is not one of its allowed values",
+                "the variable 'customer' is required",
+                "the variable 'express' must be a 'boolean' instead got 'string'",
+                "the variable 'quantity' is 0 which is outside of its allowed range",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_money_range() {
+        let schema = InputSchema::new()
+            .with_definition(
+                VariableDefinition::new("amount", Type::Money)
+                    .with_range(Some(money(0, "EUR")), Some(money(1000, "EUR"))),
+            )
+            .unwrap();
+        let valid = HashMap::from([("amount".to_string(), money(250, "EUR"))]);
+        assert!(schema.validate(&valid).is_ok());
+        let dollars = money(250, "USD");
+        for (value, expected) in [
+            (money(1500, "EUR"), "OutOfRange"),
+            (dollars, "Incomparable"),
+        ] {
+            let variables = HashMap::from([("amount".to_string(), value)]);
+            let Err(DomainError::InvalidInput { violations }) = schema.validate(&variables) else {
+                panic!("expected the input to be invalid");
+            };
+            match (&violations[..], expected) {
+                ([Violation::OutOfRange { .. }], "OutOfRange") => {}
+                ([Violation::Incomparable { .. }], "Incomparable") => {}
+                _ => panic!("expected {expected} instead got {violations:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_definition_values_must_match_the_type() {
+        let ranged = VariableDefinition::new("amount", Type::Money)
+            .with_range(Some(Value::from(0_i64)), Some(money(1000, "EUR")));
+        let defaulted =
+            VariableDefinition::new("country", Type::String).with_default(Value::from(31_i64));
+        let allowed = VariableDefinition::new("express", Type::Boolean)
+            .with_allowed_values(vec![Value::Boolean(true), Value::from("no")]);
+        let messages: Vec<String> = [ranged, defaulted, allowed]
+            .into_iter()
+            .map(|definition| InputSchema::new().with_definition(definition).unwrap_err())
+            .map(|error| error.to_string())
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "the definition of the variable 'amount' is invalid: the bound 0 is a 'number' instead of a 'money'",
+                "the definition of the variable 'country' is invalid: the default 31 is a 'number' instead of a 'string'",
+                "the definition of the variable 'express' is invalid: the allowed value no is a 'string' instead of a 'boolean'",
+            ]
+        );
+    }
+}
@@ -0,0 +1,311 @@
+use crate::error::DomainError;
+use expressive_data::error::DataError;
+use expressive_data::value::Value;
+use expressive_expression::error::ExpressionError;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter};
+
+/// The kind of error an example expects, coarse enough for rule authors to maintain.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ErrorKind {
+    /// The definition does not parse, type check or resolve.
+    InvalidDefinition,
+    /// The inputs do not match the input schema.
+    InvalidInput,
+    DivisionByZero,
+    CurrencyMismatch,
+    /// Any other error while evaluating.
+    Evaluation,
+}
+
+/// What an example expects the definition to produce.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Expectation {
+    Value(Value),
+    Error(ErrorKind),
+}
+
+/// An example of a definition: the value or kind of error it produces for some inputs.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TestCase {
+    pub name: String,
+    pub inputs: BTreeMap<String, Value>,
+    pub expected: Expectation,
+}
+
+/// A place where the value differs from the expected value, where a missing value is an element or
+/// entry that only one of them has.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValueDifference {
+    pub path: String,
+    pub expected: Option<Value>,
+    pub actual: Option<Value>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Outcome {
+    Passed,
+    WrongValue {
+        differences: Vec<ValueDifference>,
+    },
+    /// An error where a value or another kind of error was expected.
+    UnexpectedError {
+        kind: ErrorKind,
+        message: String,
+    },
+    /// A value where an error was expected.
+    MissingError {
+        expected: ErrorKind,
+        actual: Value,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TestResult {
+    pub name: String,
+    pub outcome: Outcome,
+}
+
+/// The results of running the examples of a definition, in the order they are declared.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TestReport {
+    pub results: Vec<TestResult>,
+}
+
+impl ErrorKind {
+    pub fn of(error: &DomainError) -> ErrorKind {
+        match error {
+            DomainError::InvalidInput { .. } => ErrorKind::InvalidInput,
+            DomainError::ExpressionError(error)
+            | DomainError::EvaluationFailed { source: error, .. } => match error {
+                ExpressionError::DataError(error) => ErrorKind::of_data(error),
+                ExpressionError::ParseError { .. }
+                | ExpressionError::UnknownType { .. }
+                | ExpressionError::TypeMismatch { .. } => ErrorKind::InvalidDefinition,
+                _ => ErrorKind::Evaluation,
+            },
+            DomainError::DataError(error) => ErrorKind::of_data(error),
+            _ => ErrorKind::InvalidDefinition,
+        }
+    }
+
+    fn of_data(error: &DataError) -> ErrorKind {
+        match error {
+            DataError::DivisionByZero => ErrorKind::DivisionByZero,
+            DataError::CurrencyMismatch { .. } => ErrorKind::CurrencyMismatch,
+            _ => ErrorKind::Evaluation,
+        }
+    }
+}
+
+impl TestCase {
+    pub fn new(name: impl Into<String>, expected: Expectation) -> TestCase {
+        TestCase {
+            name: name.into(),
+            inputs: BTreeMap::new(),
+            expected,
+        }
+    }
+
+    pub fn with_input(mut self, name: impl Into<String>, value: Value) -> TestCase {
+        self.inputs.insert(name.into(), value);
+        self
+    }
+
+    /// Compares the result of evaluating the definition for the inputs with the expectation.
+    pub fn check(&self, result: Result<Value, DomainError>) -> TestResult {
+        let outcome = match (&self.expected, result) {
+            (Expectation::Value(expected), Ok(actual)) => {
+                let mut differences = Vec::new();
+                diff_values("result", Some(expected), Some(&actual), &mut differences);
+                match differences.is_empty() {
+                    true => Outcome::Passed,
+                    false => Outcome::WrongValue { differences },
+                }
+            }
+            (Expectation::Error(expected), Ok(actual)) => Outcome::MissingError {
+                expected: *expected,
+                actual,
+            },
+            (Expectation::Error(expected), Err(error)) if *expected == ErrorKind::of(&error) => {
+                Outcome::Passed
+            }
+            (_, Err(error)) => Outcome::UnexpectedError {
+                kind: ErrorKind::of(&error),
+                message: error.to_string(),
+            },
+        };
+        TestResult {
+            name: self.name.clone(),
+            outcome,
+        }
+    }
+}
+
+// Compares lists element by element and maps entry by entry, so that a difference deep inside a
+// value is reported at its place instead of as a difference of the whole value.
+fn diff_values(
+    path: &str,
+    expected: Option<&Value>,
+    actual: Option<&Value>,
+    differences: &mut Vec<ValueDifference>,
+) {
+    match (expected, actual) {
+        (Some(Value::List(expected)), Some(Value::List(actual))) => {
+            for index in 0..expected.len().max(actual.len()) {
+                diff_values(
+                    &format!("{}[{}]", path, index),
+                    expected.get(index),
+                    actual.get(index),
+                    differences,
+                );
+            }
+        }
+        (Some(Value::Map(expected)), Some(Value::Map(actual))) => {
+            let keys: BTreeSet<&String> = expected.keys().chain(actual.keys()).collect();
+            for key in keys {
+                diff_values(
+                    &format!("{}.{}", path, key),
+                    expected.get(key),
+                    actual.get(key),
+                    differences,
+                );
+            }
+        }
+        (expected, actual) if expected != actual => differences.push(ValueDifference {
+            path: path.to_string(),
+            expected: expected.cloned(),
+            actual: actual.cloned(),
+        }),
+        _ => {}
+    }
+}
+
+impl TestReport {
+    pub fn passed(&self) -> bool {
+        self.failures().next().is_none()
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &TestResult> {
+        self.results
+            .iter()
+            .filter(|result| result.outcome != Outcome::Passed)
+    }
+}
+
+impl Display for ValueDifference {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let show = |value: &Option<Value>| match value {
+            Some(value) => value.to_string(),
+            None => "nothing".to_string(),
+        };
+        write!(
+            f,
+            "{} is {} instead of {}",
+            self.path,
+            show(&self.actual),
+            show(&self.expected)
+        )
+    }
+}
+
+impl Display for TestResult {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.outcome {
+            Outcome::Passed => write!(f, "'{}' passed", self.name),
+            Outcome::WrongValue { differences } => {
+                let differences: Vec<String> =
+                    differences.iter().map(ToString::to_string).collect();
+                write!(f, "'{}' failed: {}", self.name, differences.join(", "))
+            }
+            Outcome::UnexpectedError { message, .. } => {
+                write!(f, "'{}' failed with an error: {}", self.name, message)
+            }
+            Outcome::MissingError { expected, actual } => write!(
+                f,
+                "'{}' failed: expected an error of kind {:?} instead got {}",
+                self.name, expected, actual
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::definition::ExpressionDefinition;
+    use expressive_expression::types::Type;
+
+    fn number(value: i64) -> Value {
+        Value::from(value)
+    }
+
+    fn shares() -> ExpressionDefinition {
+        ExpressionDefinition::new("shares", "Shares", "map(parts, p -> total / p)")
+            .with_input("total", Type::Number)
+            .with_input("parts", Type::List(Box::new(Type::Number)))
+    }
+
+    fn run(test: TestCase) -> TestResult {
+        let definition = shares().with_test(test);
+        definition.run_tests(&Default::default()).results.remove(0)
+    }
+
+    #[test]
+    fn test_values_are_compared_by_place() {
+        let test = |expected: Vec<Value>| {
+            TestCase::new("halves", Expectation::Value(Value::List(expected)))
+                .with_input("total", number(12))
+                .with_input("parts", Value::List(vec![number(2), number(3)]))
+        };
+        assert_eq!(
+            run(test(vec![number(6), number(4)])).outcome,
+            Outcome::Passed
+        );
+        let result = run(test(vec![number(6), number(5), number(1)]));
+        assert_eq!(
+            result.to_string(),
+            "'halves' failed: result[1] is 4 instead of 5, result[2] is nothing instead of 1"
+        );
+        let mut differences = Vec::new();
+        let expected = Value::Map(BTreeMap::from([("a".to_string(), number(1))]));
+        let actual = Value::Map(BTreeMap::from([("b".to_string(), number(1))]));
+        diff_values("result", Some(&expected), Some(&actual), &mut differences);
+        assert_eq!(differences.len(), 2);
+        assert_eq!(differences[0].path, "result.a");
+    }
+
+    #[test]
+    fn test_expected_errors() {
+        let test = |expected: ErrorKind| {
+            TestCase::new("by zero", Expectation::Error(expected))
+                .with_input("total", number(1))
+                .with_input("parts", Value::List(vec![number(0)]))
+        };
+        assert_eq!(
+            run(test(ErrorKind::DivisionByZero)).outcome,
+            Outcome::Passed
+        );
+        assert!(matches!(
+            run(test(ErrorKind::InvalidInput)).outcome,
+            Outcome::UnexpectedError {
+                kind: ErrorKind::DivisionByZero,
+                ..
+            }
+        ));
+        let missing = TestCase::new("missing", Expectation::Error(ErrorKind::InvalidInput));
+        assert_eq!(run(missing).outcome, Outcome::Passed);
+        let fine = TestCase::new("fine", Expectation::Error(ErrorKind::Evaluation))
+            .with_input("total", number(1))
+            .with_input("parts", Value::List(vec![]));
+        assert_eq!(
+            run(fine).to_string(),
+            "'fine' failed: expected an error of kind Evaluation instead got []"
+        );
+    }
+}
@@ -0,0 +1,211 @@
+use crate::error::DomainError;
+use expressive_data::value::Value;
+use expressive_expression::ast::Expression;
+use expressive_expression::evaluate::{evaluate_expression, EvaluationOptions};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+/// Named formulas that refer to each other by name, like the cells of a spreadsheet.
+///
+/// A variable in a formula that is the name of another formula depends on that formula, any other
+/// variable is an input of the workbook. Formulas are evaluated after the formulas they depend on,
+/// and a formula shadows an input with the same name.
+#[derive(Clone, Debug, Default)]
+pub struct Workbook {
+    formulas: BTreeMap<String, Expression>,
+}
+
+impl Workbook {
+    pub fn new() -> Workbook {
+        Workbook::default()
+    }
+
+    /// Adds a formula, or replaces the formula with the same name.
+    pub fn insert(&mut self, name: impl Into<String>, source: &str) -> Result<(), DomainError> {
+        let expression: Expression = source.parse()?;
+        self.insert_expression(name, expression);
+        Ok(())
+    }
+
+    pub fn insert_expression(&mut self, name: impl Into<String>, expression: Expression) {
+        self.formulas.insert(name.into(), expression);
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Expression> {
+        self.formulas.remove(name)
+    }
+
+    pub fn formula(&self, name: &str) -> Option<&Expression> {
+        self.formulas.get(name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.formulas.keys().map(String::as_str)
+    }
+
+    /// The formulas that a formula refers to directly.
+    pub fn dependencies(&self, name: &str) -> BTreeSet<&str> {
+        let Some(expression) = self.formulas.get(name) else {
+            return BTreeSet::new();
+        };
+        expression
+            .variables()
+            .iter()
+            .filter_map(|variable| self.formulas.get_key_value(variable.as_str()))
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    /// The variables that the formulas refer to which are not formulas themselves.
+    pub fn inputs(&self) -> BTreeSet<String> {
+        self.formulas
+            .values()
+            .flat_map(Expression::variables)
+            .filter(|variable| !self.formulas.contains_key(variable))
+            .collect()
+    }
+
+    /// The names of all formulas, each after the formulas it depends on.
+    pub fn order(&self) -> Result<Vec<&str>, DomainError> {
+        let mut order = Vec::with_capacity(self.formulas.len());
+        let mut done = HashSet::new();
+        for name in self.formulas.keys() {
+            self.visit(name, &mut Vec::new(), &mut done, &mut order)?;
+        }
+        Ok(order)
+    }
+
+    // A depth-first search where the path holds the formulas being visited, so that reaching one of
+    // them again closes a cycle.
+    fn visit<'a>(
+        &'a self,
+        name: &'a str,
+        path: &mut Vec<&'a str>,
+        done: &mut HashSet<&'a str>,
+        order: &mut Vec<&'a str>,
+    ) -> Result<(), DomainError> {
+        if done.contains(name) {
+            return Ok(());
+        }
+        if let Some(start) = path.iter().position(|visiting| *visiting == name) {
+            let mut cycle: Vec<String> = path[start..].iter().map(|n| n.to_string()).collect();
+            cycle.push(name.to_string());
+            return Err(DomainError::CyclicDependency { path: cycle });
+        }
+        path.push(name);
+        for dependency in self.dependencies(name) {
+            self.visit(dependency, path, done, order)?;
+        }
+        path.pop();
+        done.insert(name);
+        order.push(name);
+        Ok(())
+    }
+
+    /// Evaluates all formulas for the given inputs and returns their values by name.
+    pub fn evaluate(
+        &self,
+        inputs: &HashMap<String, Value>,
+    ) -> Result<BTreeMap<String, Value>, DomainError> {
+        let mut variables = inputs.clone();
+        let mut results = BTreeMap::new();
+        for name in self.order()? {
+            let value = self.evaluate_formula(name, &variables)?;
+            variables.insert(name.to_string(), value.clone());
+            results.insert(name.to_string(), value);
+        }
+        Ok(results)
+    }
+
+    /// Evaluates a single formula, where the variables must hold the values of its dependencies.
+    pub(crate) fn evaluate_formula(
+        &self,
+        name: &str,
+        variables: &HashMap<String, Value>,
+    ) -> Result<Value, DomainError> {
+        let expression =
+            self.formulas
+                .get(name)
+                .ok_or_else(|| DomainError::UndefinedDefinition {
+                    id: name.to_string(),
+                })?;
+        evaluate_expression(expression, variables, &EvaluationOptions::default()).map_err(
+            |source| DomainError::EvaluationFailed {
+                name: name.to_string(),
+                source,
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quote() -> Workbook {
+        let mut workbook = Workbook::new();
+        workbook.insert("total", "subtotal + vat").unwrap();
+        workbook.insert("vat", "subtotal * vat_rate").unwrap();
+        workbook
+            .insert("subtotal", "sum(map(quantities, q -> q * unit_price))")
+            .unwrap();
+        workbook.insert("unit_price", "12.50").unwrap();
+        workbook
+    }
+
+    #[test]
+    fn test_order_follows_dependencies() {
+        let workbook = quote();
+        assert_eq!(
+            workbook.order().unwrap(),
+            ["unit_price", "subtotal", "vat", "total"]
+        );
+        assert_eq!(
+            workbook
+                .dependencies("total")
+                .into_iter()
+                .collect::<Vec<_>>(),
+            ["subtotal", "vat"]
+        );
+        assert_eq!(
+            workbook.inputs().into_iter().collect::<Vec<_>>(),
+            ["quantities", "vat_rate"]
+        );
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let workbook = quote();
+        let inputs = HashMap::from([
+            (
+                "quantities".to_string(),
+                Value::List(vec![Value::from(2_i64), Value::from(4_i64)]),
+            ),
+            (
+                "vat_rate".to_string(),
+                Value::Decimal("0.25".parse().unwrap()),
+            ),
+        ]);
+        let results = workbook.evaluate(&inputs).unwrap();
+        assert_eq!(results["subtotal"].to_string(), "75.00");
+        assert_eq!(results["total"].to_string(), "93.7500");
+        let error = workbook.evaluate(&HashMap::new()).unwrap_err();
+        assert!(matches!(error, DomainError::EvaluationFailed { name, .. } if name == "subtotal"));
+    }
+
+    #[test]
+    fn test_cycles_report_their_path() {
+        let mut workbook = quote();
+        workbook.insert("unit_price", "total / 10").unwrap();
+        let error = workbook.order().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "the formulas depend on each other in a cycle: subtotal -> unit_price -> total -> \
+             subtotal"
+        );
+        workbook.insert("unit_price", "unit_price + 1").unwrap();
+        assert!(matches!(
+            workbook.order(),
+            Err(DomainError::CyclicDependency { path }) if path == ["unit_price", "unit_price"]
+        ));
+    }
+}
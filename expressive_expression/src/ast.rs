@@ -0,0 +1,603 @@
+use crate::error::ExpressionError;
+use expressive_data::value::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// The abstract syntax tree of a parsed expression.
+///
+/// Parentheses are not kept in the tree, the structure of the tree reflects the grouping instead.
+/// Displaying an expression writes it back as source text with the parentheses it needs.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+pub enum Expression {
+    Literal {
+        value: Value,
+    },
+    Variable {
+        name: String,
+    },
+    List {
+        items: Vec<Expression>,
+    },
+    FunctionCall {
+        name: String,
+        arguments: Vec<Expression>,
+    },
+    /// A lambda, which can only be passed as an argument to a higher-order function.
+    Lambda {
+        parameters: Vec<String>,
+        body: Box<Expression>,
+    },
+    Access {
+        target: Box<Expression>,
+        accessor: Accessor,
+    },
+    Binary {
+        operator: BinaryOperator,
+        left: Box<Expression>,
+        right: Box<Expression>,
+    },
+    Prefix {
+        operator: PrefixOperator,
+        operand: Box<Expression>,
+    },
+    Postfix {
+        operator: PostfixOperator,
+        operand: Box<Expression>,
+    },
+}
+
+/// A user-defined function, e.g. `fn net(gross, vat) = gross / (1 + vat)`, whose calls are replaced
+/// by its body with the arguments substituted for the parameters.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FunctionDefinition {
+    pub name: String,
+    pub parameters: Vec<String>,
+    pub body: Expression,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Accessor {
+    Field(String),
+    Index(Box<Expression>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum BinaryOperator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Power,
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    In,
+    And,
+    Or,
+    Coalesce,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum PrefixOperator {
+    Not,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum PostfixOperator {
+    IsNull,
+    IsNotNull,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Associativity {
+    Left,
+    Right,
+}
+
+// The precedence of all operators, where a higher precedence binds tighter. The parser is built
+// from the precedence and associativity of the operators, so this is the only place they are
+// declared.
+const OR: u32 = 1;
+const AND: u32 = 2;
+const NOT: u32 = 3;
+const COMPARISON: u32 = 4;
+const COALESCE: u32 = 5;
+const ADDITIVE: u32 = 6;
+const MULTIPLICATIVE: u32 = 7;
+const POWER: u32 = 8;
+
+impl BinaryOperator {
+    /// How tight the operator binds, a higher precedence binds tighter.
+    pub fn precedence(&self) -> u32 {
+        match self {
+            BinaryOperator::Power => POWER,
+            BinaryOperator::Multiply | BinaryOperator::Divide => MULTIPLICATIVE,
+            BinaryOperator::Add | BinaryOperator::Subtract => ADDITIVE,
+            BinaryOperator::Coalesce => COALESCE,
+            BinaryOperator::Equal
+            | BinaryOperator::NotEqual
+            | BinaryOperator::Less
+            | BinaryOperator::LessOrEqual
+            | BinaryOperator::Greater
+            | BinaryOperator::GreaterOrEqual
+            | BinaryOperator::In => COMPARISON,
+            BinaryOperator::And => AND,
+            BinaryOperator::Or => OR,
+        }
+    }
+
+    /// Power is right associative, so `2 ^ 3 ^ 2` is `2 ^ (3 ^ 2)`, all other operators are left
+    /// associative.
+    pub fn associativity(&self) -> Associativity {
+        match self {
+            BinaryOperator::Power => Associativity::Right,
+            _ => Associativity::Left,
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            BinaryOperator::Add => "+",
+            BinaryOperator::Subtract => "-",
+            BinaryOperator::Multiply => "*",
+            BinaryOperator::Divide => "/",
+            BinaryOperator::Power => "^",
+            BinaryOperator::Equal => "==",
+            BinaryOperator::NotEqual => "!=",
+            BinaryOperator::Less => "<",
+            BinaryOperator::LessOrEqual => "<=",
+            BinaryOperator::Greater => ">",
+            BinaryOperator::GreaterOrEqual => ">=",
+            BinaryOperator::In => "in",
+            BinaryOperator::And => "and",
+            BinaryOperator::Or => "or",
+            BinaryOperator::Coalesce => "??",
+        }
+    }
+}
+
+impl PrefixOperator {
+    /// Negation binds looser than comparisons, so `not a == b` is `not (a == b)`.
+    pub fn precedence(&self) -> u32 {
+        NOT
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            PrefixOperator::Not => "not",
+        }
+    }
+}
+
+impl PostfixOperator {
+    /// Postfix operators bind like the comparison operators.
+    pub fn precedence(&self) -> u32 {
+        COMPARISON
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            PostfixOperator::IsNull => "is null",
+            PostfixOperator::IsNotNull => "is not null",
+        }
+    }
+}
+
+impl Expression {
+    /// The names of the variables the expression refers to, without the parameters of its lambdas.
+    pub fn variables(&self) -> BTreeSet<String> {
+        let mut variables = BTreeSet::new();
+        self.collect_variables(&mut Vec::new(), &mut variables);
+        variables
+    }
+
+    fn collect_variables<'a>(&'a self, bound: &mut Vec<&'a str>, variables: &mut BTreeSet<String>) {
+        match self {
+            Expression::Literal { .. } => {}
+            Expression::Variable { name } => {
+                if !bound.contains(&name.as_str()) {
+                    variables.insert(name.clone());
+                }
+            }
+            Expression::List { items: expressions }
+            | Expression::FunctionCall {
+                arguments: expressions,
+                ..
+            } => {
+                for expression in expressions {
+                    expression.collect_variables(bound, variables);
+                }
+            }
+            Expression::Lambda { parameters, body } => {
+                let depth = bound.len();
+                bound.extend(parameters.iter().map(String::as_str));
+                body.collect_variables(bound, variables);
+                bound.truncate(depth);
+            }
+            Expression::Access { target, accessor } => {
+                target.collect_variables(bound, variables);
+                if let Accessor::Index(index) = accessor {
+                    index.collect_variables(bound, variables);
+                }
+            }
+            Expression::Binary { left, right, .. } => {
+                left.collect_variables(bound, variables);
+                right.collect_variables(bound, variables);
+            }
+            Expression::Prefix { operand, .. } | Expression::Postfix { operand, .. } => {
+                operand.collect_variables(bound, variables);
+            }
+        }
+    }
+
+    /// The number of nodes in the tree.
+    pub fn size(&self) -> usize {
+        1 + match self {
+            Expression::Literal { .. } | Expression::Variable { .. } => 0,
+            Expression::List { items: expressions }
+            | Expression::FunctionCall {
+                arguments: expressions,
+                ..
+            } => expressions.iter().map(Expression::size).sum(),
+            Expression::Lambda { body, .. } => body.size(),
+            Expression::Access { target, accessor } => {
+                target.size()
+                    + match accessor {
+                        Accessor::Field(_) => 0,
+                        Accessor::Index(index) => index.size(),
+                    }
+            }
+            Expression::Binary { left, right, .. } => left.size() + right.size(),
+            Expression::Prefix { operand, .. } | Expression::Postfix { operand, .. } => {
+                operand.size()
+            }
+        }
+    }
+
+    /// Replaces the variables that are bound to an expression. Lambda parameters shadow the
+    /// bindings and are renamed when they would capture a variable of a substituted expression.
+    pub fn substitute(&self, bindings: &HashMap<String, Expression>) -> Expression {
+        let substitute_all = |expressions: &[Expression]| {
+            expressions
+                .iter()
+                .map(|expression| expression.substitute(bindings))
+                .collect()
+        };
+        match self {
+            Expression::Literal { .. } => self.clone(),
+            Expression::Variable { name } => bindings.get(name).unwrap_or(self).clone(),
+            Expression::List { items } => Expression::List {
+                items: substitute_all(items),
+            },
+            Expression::FunctionCall { name, arguments } => Expression::FunctionCall {
+                name: name.clone(),
+                arguments: substitute_all(arguments),
+            },
+            Expression::Lambda { parameters, body } => {
+                let captured: BTreeSet<String> = bindings
+                    .iter()
+                    .filter(|(name, _)| !parameters.contains(name))
+                    .flat_map(|(_, expression)| expression.variables())
+                    .collect();
+                let mut scope = bindings.clone();
+                let mut renamed = Vec::with_capacity(parameters.len());
+                for parameter in parameters {
+                    scope.remove(parameter);
+                    if !captured.contains(parameter) {
+                        renamed.push(parameter.clone());
+                        continue;
+                    }
+                    let used = body.variables();
+                    let fresh = (1..)
+                        .map(|n| format!("{}_{}", parameter, n))
+                        .find(|name| {
+                            !captured.contains(name)
+                                && !used.contains(name)
+                                && !parameters.contains(name)
+                                && !renamed.contains(name)
+                        })
+                        .unwrap_or_default();
+                    scope.insert(
+                        parameter.clone(),
+                        Expression::Variable {
+                            name: fresh.clone(),
+                        },
+                    );
+                    renamed.push(fresh);
+                }
+                Expression::Lambda {
+                    parameters: renamed,
+                    body: Box::new(body.substitute(&scope)),
+                }
+            }
+            Expression::Access { target, accessor } => Expression::Access {
+                target: Box::new(target.substitute(bindings)),
+                accessor: match accessor {
+                    Accessor::Field(name) => Accessor::Field(name.clone()),
+                    Accessor::Index(index) => Accessor::Index(Box::new(index.substitute(bindings))),
+                },
+            },
+            Expression::Binary {
+                operator,
+                left,
+                right,
+            } => Expression::Binary {
+                operator: *operator,
+                left: Box::new(left.substitute(bindings)),
+                right: Box::new(right.substitute(bindings)),
+            },
+            Expression::Prefix { operator, operand } => Expression::Prefix {
+                operator: *operator,
+                operand: Box::new(operand.substitute(bindings)),
+            },
+            Expression::Postfix { operator, operand } => Expression::Postfix {
+                operator: *operator,
+                operand: Box::new(operand.substitute(bindings)),
+            },
+        }
+    }
+
+    fn precedence(&self) -> u32 {
+        match self {
+            Expression::Binary { operator, .. } => operator.precedence(),
+            Expression::Prefix { operator, .. } => operator.precedence(),
+            Expression::Postfix { operator, .. } => operator.precedence(),
+            Expression::Lambda { .. } => 0,
+            _ => u32::MAX,
+        }
+    }
+}
+
+impl FromStr for Expression {
+    type Err = ExpressionError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        crate::parser::parse(input)
+    }
+}
+
+impl FromStr for FunctionDefinition {
+    type Err = ExpressionError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        crate::parser::parse_function(input)
+    }
+}
+
+impl Display for FunctionDefinition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "fn {}({}) = {}",
+            self.name,
+            self.parameters.join(", "),
+            self.body
+        )
+    }
+}
+
+fn write_grouped(
+    f: &mut Formatter<'_>,
+    expression: &Expression,
+    grouped: bool,
+) -> std::fmt::Result {
+    match grouped {
+        true => write!(f, "({})", expression),
+        false => write!(f, "{}", expression),
+    }
+}
+
+fn write_separated(f: &mut Formatter<'_>, items: &[Expression]) -> std::fmt::Result {
+    for (index, item) in items.iter().enumerate() {
+        if index > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{}", item)?;
+    }
+    Ok(())
+}
+
+impl Display for Expression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expression::Literal {
+                value: Value::String(s),
+            } => write!(f, "\"{}\"", s),
+            Expression::Literal { value } => write!(f, "{}", value),
+            Expression::Variable { name } => write!(f, "{}", name),
+            Expression::List { items } => {
+                write!(f, "[")?;
+                write_separated(f, items)?;
+                write!(f, "]")
+            }
+            Expression::FunctionCall { name, arguments } => {
+                write!(f, "{}(", name)?;
+                write_separated(f, arguments)?;
+                write!(f, ")")
+            }
+            Expression::Lambda { parameters, body } => match parameters.as_slice() {
+                [parameter] => write!(f, "{} -> {}", parameter, body),
+                _ => write!(f, "({}) -> {}", parameters.join(", "), body),
+            },
+            Expression::Access { target, accessor } => {
+                write_grouped(f, target, target.precedence() < u32::MAX)?;
+                match accessor {
+                    Accessor::Field(name) => write!(f, ".{}", name),
+                    Accessor::Index(index) => write!(f, "[{}]", index),
+                }
+            }
+            Expression::Binary {
+                operator,
+                left,
+                right,
+            } => {
+                let (left_grouped, right_grouped) = match operator.associativity() {
+                    Associativity::Left => (
+                        left.precedence() < operator.precedence(),
+                        right.precedence() <= operator.precedence(),
+                    ),
+                    Associativity::Right => (
+                        left.precedence() <= operator.precedence(),
+                        right.precedence() < operator.precedence(),
+                    ),
+                };
+                write_grouped(f, left, left_grouped)?;
+                write!(f, " {} ", operator.symbol())?;
+                write_grouped(f, right, right_grouped)
+            }
+            Expression::Prefix { operator, operand } => {
+                write!(f, "{} ", operator.symbol())?;
+                write_grouped(f, operand, operand.precedence() < operator.precedence())
+            }
+            Expression::Postfix { operator, operand } => {
+                write_grouped(f, operand, operand.precedence() < operator.precedence())?;
+                write!(f, " {}", operator.symbol())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_round_trip() {
+        let inputs = [
+            "(a + b) * c",
+            "a - (b - c)",
+            "a - b - c",
+            "EUR 12.50 + price * 2",
+            "(a or b) is not null",
+            "customer.address[\"zip code\"] == \"1234\" or false",
+            "sum(map(lines, (x, y) -> x.price * x.qty)) > 100",
+            "(1 + 2)[0] + [1, 2.5, 5 kg][-1]",
+            "2 ^ 3 ^ 2 * (2 ^ 3) ^ 2",
+            "not a == b and not (c or d)",
+            "not a is null",
+            "42",
+            "price",
+            "customer.name",
+        ];
+        for input in inputs {
+            let expression: Expression = input.parse().unwrap();
+            assert_eq!(expression.to_string(), input);
+            let reparsed: Expression = expression.to_string().parse().unwrap();
+            assert_eq!(reparsed, expression, "input = {}", input);
+        }
+    }
+
+    #[test]
+    fn test_parse_builds_tree() {
+        let expression: Expression = "a + b * 2 is null".parse().unwrap();
+        let expected = Expression::Postfix {
+            operator: PostfixOperator::IsNull,
+            operand: Box::new(Expression::Binary {
+                operator: BinaryOperator::Add,
+                left: Box::new(Expression::Variable { name: "a".into() }),
+                right: Box::new(Expression::Binary {
+                    operator: BinaryOperator::Multiply,
+                    left: Box::new(Expression::Variable { name: "b".into() }),
+                    right: Box::new(Expression::Literal {
+                        value: Value::Integer(2.into()),
+                    }),
+                }),
+            }),
+        };
+        assert_eq!(expression, expected);
+    }
+
+    #[test]
+    fn test_parse_single_terminal() {
+        let expression: Expression = "((price))".parse().unwrap();
+        assert_eq!(
+            expression,
+            Expression::Variable {
+                name: "price".into()
+            }
+        );
+        assert_eq!(expression.to_string(), "price");
+    }
+
+    #[test]
+    fn test_variables() {
+        let expression: Expression =
+            "sum(map(lines, line -> line.price * rate)) + lines[index] ?? fallback"
+                .parse()
+                .unwrap();
+        let variables: Vec<String> = expression.variables().into_iter().collect();
+        assert_eq!(variables, ["fallback", "index", "lines", "rate"]);
+    }
+
+    #[test]
+    fn test_size() {
+        let size = |source: &str| source.parse::<Expression>().unwrap().size();
+        assert_eq!(size("a * (b + 1)"), 5);
+        assert_eq!(size("items[0].price"), 4);
+        assert_eq!(size("map(xs, x -> not x)"), 5);
+    }
+
+    #[test]
+    fn test_parse_function_definition() {
+        let function: FunctionDefinition =
+            "fn net(gross, vat) = gross / (1 + vat)".parse().unwrap();
+        assert_eq!(function.name, "net");
+        assert_eq!(function.parameters, ["gross", "vat"]);
+        assert_eq!(
+            function.to_string(),
+            "fn net(gross, vat) = gross / (1 + vat)"
+        );
+        let function: FunctionDefinition = "fn zero() = 0".parse().unwrap();
+        assert!(function.parameters.is_empty());
+        assert!("fn net(gross) gross".parse::<FunctionDefinition>().is_err());
+        assert!("fnnet(gross) = gross"
+            .parse::<FunctionDefinition>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_substitute() {
+        let expression: Expression = "map(items, x -> x * rate) + [rate, x_1]".parse().unwrap();
+        let bindings = HashMap::from([
+            ("rate".to_string(), "x + 1".parse().unwrap()),
+            ("items".to_string(), "lines".parse().unwrap()),
+        ]);
+        assert_eq!(
+            expression.substitute(&bindings).to_string(),
+            "map(lines, x_1 -> x_1 * (x + 1)) + [x + 1, x_1]"
+        );
+        let expression: Expression = "reduce(items, (x, x_1) -> x + rate)".parse().unwrap();
+        let bindings = HashMap::from([("rate".to_string(), "x".parse().unwrap())]);
+        assert_eq!(
+            expression.substitute(&bindings).to_string(),
+            "reduce(items, (x_2, x_1) -> x_2 + x)"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let expression: Expression = "sum(map(lines, x -> x.price * 1.10)) ?? EUR 0.00"
+            .parse()
+            .unwrap();
+        let json = serde_json::to_string(&expression).unwrap();
+        let deserialized: Expression = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, expression);
+        assert!(json.contains(r#"{"kind":"literal","value":{"decimal":"1.10"}}"#));
+    }
+}
@@ -0,0 +1,647 @@
+use crate::ast::{Accessor, BinaryOperator, Expression};
+use expressive_data::value::Value;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+/// A change between two versions of an expression, found by comparing their trees so that changes
+/// in whitespace or parentheses that do not change the grouping are not changes.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+pub enum Change {
+    ConstantChanged {
+        before: Value,
+        after: Value,
+    },
+    VariableRenamed {
+        before: String,
+        after: String,
+    },
+    FunctionRenamed {
+        before: String,
+        after: String,
+    },
+    FieldRenamed {
+        before: String,
+        after: String,
+    },
+    OperatorChanged {
+        before: String,
+        after: String,
+    },
+    /// An operand that was joined to the expression, e.g. a condition joined to the existing ones
+    /// with `and`, on the side of the operator it is on.
+    Added {
+        operator: BinaryOperator,
+        side: Side,
+        expression: Expression,
+    },
+    Removed {
+        operator: BinaryOperator,
+        side: Side,
+        expression: Expression,
+    },
+    /// An element of a list or an argument of a call that was added.
+    ElementAdded {
+        expression: Expression,
+    },
+    ElementRemoved {
+        expression: Expression,
+    },
+    Replaced {
+        before: Expression,
+        after: Expression,
+    },
+}
+
+/// The side of a binary operator that an added or removed operand is on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Side {
+    Left,
+    Right,
+}
+
+/// The changes that turn one expression into the other, in the order they appear in the source. A
+/// change that is made in several places, like renaming a variable, is reported once.
+///
+/// A variable is only reported as renamed when all of its occurrences changed to a name that was
+/// not used before, otherwise every changed occurrence is reported as replaced.
+pub fn diff(before: &Expression, after: &Expression) -> Vec<Change> {
+    let mut differ = Differ::default();
+    differ.expressions(before, after);
+    let Differ {
+        mut changes,
+        renames,
+    } = differ;
+    let used = before.variables();
+    let replaced: Vec<usize> = renames
+        .iter()
+        .copied()
+        .filter(|index| match &changes[*index] {
+            Change::VariableRenamed {
+                before: old,
+                after: new,
+            } => {
+                let renamed = renames
+                    .iter()
+                    .filter(|other| changes[**other] == changes[*index])
+                    .count();
+                renamed != occurrences(before, old) || used.contains(new)
+            }
+            _ => false,
+        })
+        .collect();
+    for index in replaced {
+        if let Change::VariableRenamed { before, after } = &changes[index] {
+            changes[index] = Change::Replaced {
+                before: Expression::Variable {
+                    name: before.clone(),
+                },
+                after: Expression::Variable {
+                    name: after.clone(),
+                },
+            };
+        }
+    }
+    let mut unique: Vec<Change> = Vec::with_capacity(changes.len());
+    for change in changes {
+        if !unique.contains(&change) {
+            unique.push(change);
+        }
+    }
+    unique
+}
+
+// The number of places where a variable is referred to outside of the lambdas that bind its name.
+fn occurrences(expression: &Expression, name: &str) -> usize {
+    let count_all = |expressions: &[Expression]| {
+        expressions
+            .iter()
+            .map(|expression| occurrences(expression, name))
+            .sum()
+    };
+    match expression {
+        Expression::Literal { .. } => 0,
+        Expression::Variable { name: other } => usize::from(other == name),
+        Expression::List { items: expressions }
+        | Expression::FunctionCall {
+            arguments: expressions,
+            ..
+        } => count_all(expressions),
+        Expression::Lambda { parameters, body } => match parameters.iter().any(|p| p == name) {
+            true => 0,
+            false => occurrences(body, name),
+        },
+        Expression::Access { target, accessor } => {
+            occurrences(target, name)
+                + match accessor {
+                    Accessor::Field(_) => 0,
+                    Accessor::Index(index) => occurrences(index, name),
+                }
+        }
+        Expression::Binary { left, right, .. } => {
+            occurrences(left, name) + occurrences(right, name)
+        }
+        Expression::Prefix { operand, .. } | Expression::Postfix { operand, .. } => {
+            occurrences(operand, name)
+        }
+    }
+}
+
+// Collects the changes in source order, together with the places of the changes that rename a
+// free variable, which are only renames when all occurrences of the variable changed alike.
+#[derive(Default)]
+struct Differ {
+    changes: Vec<Change>,
+    renames: Vec<usize>,
+}
+
+impl Differ {
+    fn push(&mut self, change: Change) {
+        self.changes.push(change);
+    }
+
+    fn expressions(&mut self, before: &Expression, after: &Expression) {
+        if before == after {
+            return;
+        }
+        // An operand that was joined to or split off from an unchanged expression.
+        if let Expression::Binary {
+            operator,
+            left,
+            right,
+        } = after
+        {
+            if **left == *before || **right == *before {
+                let (side, added) = match **left == *before {
+                    true => (Side::Right, right),
+                    false => (Side::Left, left),
+                };
+                self.push(Change::Added {
+                    operator: *operator,
+                    side,
+                    expression: *added.clone(),
+                });
+                return;
+            }
+        }
+        if let Expression::Binary {
+            operator,
+            left,
+            right,
+        } = before
+        {
+            if **left == *after || **right == *after {
+                let (side, removed) = match **left == *after {
+                    true => (Side::Right, right),
+                    false => (Side::Left, left),
+                };
+                self.push(Change::Removed {
+                    operator: *operator,
+                    side,
+                    expression: *removed.clone(),
+                });
+                return;
+            }
+        }
+        match (before, after) {
+            (Expression::Literal { value: before }, Expression::Literal { value: after }) => self
+                .push(Change::ConstantChanged {
+                    before: before.clone(),
+                    after: after.clone(),
+                }),
+            (Expression::Variable { name: before }, Expression::Variable { name: after }) => {
+                self.renames.push(self.changes.len());
+                self.push(Change::VariableRenamed {
+                    before: before.clone(),
+                    after: after.clone(),
+                })
+            }
+            (Expression::List { items: before }, Expression::List { items: after }) => {
+                self.sequences(before, after)
+            }
+            (
+                Expression::FunctionCall {
+                    name: before_name,
+                    arguments: before_arguments,
+                },
+                Expression::FunctionCall {
+                    name: after_name,
+                    arguments: after_arguments,
+                },
+            ) => {
+                if before_name != after_name {
+                    self.push(Change::FunctionRenamed {
+                        before: before_name.clone(),
+                        after: after_name.clone(),
+                    });
+                }
+                self.sequences(before_arguments, after_arguments)
+            }
+            (
+                Expression::Lambda {
+                    parameters: before_parameters,
+                    body: before_body,
+                },
+                Expression::Lambda {
+                    parameters: after_parameters,
+                    body: after_body,
+                },
+            ) if before_parameters.len() == after_parameters.len() => {
+                // Renamed parameters are compared under their new names, so that only the renames
+                // themselves are reported. A new name that the lambda already uses for a variable
+                // from outside would capture it, so then the lambda was replaced.
+                let outside = before.variables();
+                let mut bindings = HashMap::new();
+                let mut renames = Vec::new();
+                for (old, new) in before_parameters.iter().zip(after_parameters) {
+                    if old == new {
+                        continue;
+                    }
+                    if outside.contains(new) {
+                        return self.replaced(before, after);
+                    }
+                    renames.push(Change::VariableRenamed {
+                        before: old.clone(),
+                        after: new.clone(),
+                    });
+                    bindings.insert(old.clone(), Expression::Variable { name: new.clone() });
+                }
+                self.changes.extend(renames);
+                self.expressions(&before_body.substitute(&bindings), after_body)
+            }
+            (
+                Expression::Access {
+                    target: before_target,
+                    accessor: before_accessor,
+                },
+                Expression::Access {
+                    target: after_target,
+                    accessor: after_accessor,
+                },
+            ) => {
+                self.expressions(before_target, after_target);
+                match (before_accessor, after_accessor) {
+                    (Accessor::Field(before), Accessor::Field(after)) if before != after => self
+                        .push(Change::FieldRenamed {
+                            before: before.clone(),
+                            after: after.clone(),
+                        }),
+                    (Accessor::Index(before), Accessor::Index(after)) => {
+                        self.expressions(before, after)
+                    }
+                    (Accessor::Field(_), Accessor::Field(_)) => {}
+                    _ => self.replaced(before, after),
+                }
+            }
+            (
+                Expression::Binary {
+                    operator: before_operator,
+                    left: before_left,
+                    right: before_right,
+                },
+                Expression::Binary {
+                    operator: after_operator,
+                    left: after_left,
+                    right: after_right,
+                },
+            ) => {
+                if before_operator != after_operator {
+                    // An operand joined to a changed expression, e.g. `age >= 65` that became
+                    // `age >= 67 and member`.
+                    for (joined, other, side) in [
+                        (after_left, after_right, Side::Right),
+                        (after_right, after_left, Side::Left),
+                    ] {
+                        if has_operator(joined, before_operator) {
+                            self.expressions(before, joined);
+                            self.push(Change::Added {
+                                operator: *after_operator,
+                                side,
+                                expression: *other.clone(),
+                            });
+                            return;
+                        }
+                    }
+                    for (split, other, side) in [
+                        (before_left, before_right, Side::Right),
+                        (before_right, before_left, Side::Left),
+                    ] {
+                        if has_operator(split, after_operator) {
+                            self.expressions(split, after);
+                            self.push(Change::Removed {
+                                operator: *before_operator,
+                                side,
+                                expression: *other.clone(),
+                            });
+                            return;
+                        }
+                    }
+                    self.push(Change::OperatorChanged {
+                        before: before_operator.symbol().to_string(),
+                        after: after_operator.symbol().to_string(),
+                    });
+                }
+                self.expressions(before_left, after_left);
+                self.expressions(before_right, after_right);
+            }
+            (
+                Expression::Prefix {
+                    operator: before_operator,
+                    operand: before_operand,
+                },
+                Expression::Prefix {
+                    operator: after_operator,
+                    operand: after_operand,
+                },
+            ) if before_operator == after_operator => {
+                self.expressions(before_operand, after_operand)
+            }
+            (
+                Expression::Postfix {
+                    operator: before_operator,
+                    operand: before_operand,
+                },
+                Expression::Postfix {
+                    operator: after_operator,
+                    operand: after_operand,
+                },
+            ) => {
+                if before_operator != after_operator {
+                    self.push(Change::OperatorChanged {
+                        before: before_operator.symbol().to_string(),
+                        after: after_operator.symbol().to_string(),
+                    });
+                }
+                self.expressions(before_operand, after_operand)
+            }
+            _ => self.replaced(before, after),
+        }
+    }
+
+    fn replaced(&mut self, before: &Expression, after: &Expression) {
+        self.push(Change::Replaced {
+            before: before.clone(),
+            after: after.clone(),
+        });
+    }
+
+    // Matches the longest common subsequence of unchanged items, and compares the items in between
+    // pairwise, where the items left over were added or removed.
+    fn sequences(&mut self, before: &[Expression], after: &[Expression]) {
+        let mut common = vec![vec![0; after.len() + 1]; before.len() + 1];
+        for i in (0..before.len()).rev() {
+            for j in (0..after.len()).rev() {
+                common[i][j] = match before[i] == after[j] {
+                    true => common[i + 1][j + 1] + 1,
+                    false => common[i + 1][j].max(common[i][j + 1]),
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        let (mut removed, mut added) = (Vec::new(), Vec::new());
+        loop {
+            let matched = i < before.len() && j < after.len() && before[i] == after[j];
+            if matched || (i == before.len() && j == after.len()) {
+                self.segment(&removed, &added);
+                removed.clear();
+                added.clear();
+                if !matched {
+                    return;
+                }
+                i += 1;
+                j += 1;
+            } else if j == after.len() || (i < before.len() && common[i + 1][j] >= common[i][j + 1])
+            {
+                removed.push(&before[i]);
+                i += 1;
+            } else {
+                added.push(&after[j]);
+                j += 1;
+            }
+        }
+    }
+
+    fn segment(&mut self, removed: &[&Expression], added: &[&Expression]) {
+        for (before, after) in removed.iter().zip(added) {
+            self.expressions(before, after);
+        }
+        for expression in &removed[added.len().min(removed.len())..] {
+            self.push(Change::ElementRemoved {
+                expression: (*expression).clone(),
+            });
+        }
+        for expression in &added[removed.len().min(added.len())..] {
+            self.push(Change::ElementAdded {
+                expression: (*expression).clone(),
+            });
+        }
+    }
+}
+
+fn has_operator(expression: &Expression, operator: &BinaryOperator) -> bool {
+    matches!(expression, Expression::Binary { operator: other, .. } if other == operator)
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let constant = |value: &Value| Expression::Literal {
+            value: value.clone(),
+        };
+        match self {
+            Change::ConstantChanged { before, after } => write!(
+                f,
+                "changed the constant {} to {}",
+                constant(before),
+                constant(after)
+            ),
+            Change::VariableRenamed { before, after } => {
+                write!(f, "renamed the variable '{}' to '{}'", before, after)
+            }
+            Change::FunctionRenamed { before, after } => {
+                write!(f, "replaced the function '{}' by '{}'", before, after)
+            }
+            Change::FieldRenamed { before, after } => {
+                write!(f, "changed the field '{}' to '{}'", before, after)
+            }
+            Change::OperatorChanged { before, after } => {
+                write!(f, "changed the operator '{}' to '{}'", before, after)
+            }
+            Change::Added {
+                operator,
+                side,
+                expression,
+            } => match (operator, side) {
+                (BinaryOperator::Subtract, Side::Right) => {
+                    write!(f, "subtracted '{}'", expression)
+                }
+                (BinaryOperator::Subtract, Side::Left) => {
+                    write!(f, "subtracted from '{}'", expression)
+                }
+                (BinaryOperator::Multiply, _) => write!(f, "multiplied by '{}'", expression),
+                (BinaryOperator::Divide, Side::Right) => write!(f, "divided by '{}'", expression),
+                (BinaryOperator::Divide, Side::Left) => {
+                    write!(f, "divided '{}' by it", expression)
+                }
+                (BinaryOperator::Power, Side::Right) => {
+                    write!(f, "raised to the power '{}'", expression)
+                }
+                (BinaryOperator::Power, Side::Left) => {
+                    write!(f, "raised '{}' to its power", expression)
+                }
+                (_, Side::Right) => write!(f, "added '{} {}'", operator.symbol(), expression),
+                (_, Side::Left) => write!(f, "added '{} {}'", expression, operator.symbol()),
+            },
+            Change::Removed {
+                operator,
+                side,
+                expression,
+            } => match (operator, side) {
+                (BinaryOperator::Subtract, Side::Right) => {
+                    write!(f, "removed the subtraction of '{}'", expression)
+                }
+                (BinaryOperator::Subtract, Side::Left) => {
+                    write!(f, "removed the subtraction from '{}'", expression)
+                }
+                (BinaryOperator::Multiply, _) => {
+                    write!(f, "removed the multiplication by '{}'", expression)
+                }
+                (BinaryOperator::Divide, Side::Right) => {
+                    write!(f, "removed the division by '{}'", expression)
+                }
+                (BinaryOperator::Divide, Side::Left) => {
+                    write!(f, "removed the division of '{}'", expression)
+                }
+                (BinaryOperator::Power, Side::Right) => {
+                    write!(f, "removed the exponent '{}'", expression)
+                }
+                (BinaryOperator::Power, Side::Left) => {
+                    write!(f, "removed the base '{}'", expression)
+                }
+                (_, Side::Right) => write!(f, "removed '{} {}'", operator.symbol(), expression),
+                (_, Side::Left) => write!(f, "removed '{} {}'", expression, operator.symbol()),
+            },
+            Change::ElementAdded { expression } => write!(f, "added '{}'", expression),
+            Change::ElementRemoved { expression } => write!(f, "removed '{}'", expression),
+            Change::Replaced { before, after } => write!(f, "replaced '{}' by '{}'", before, after),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn changes(before: &str, after: &str) -> Vec<String> {
+        let before: Expression = before.parse().unwrap();
+        let after: Expression = after.parse().unwrap();
+        diff(&before, &after)
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_formatting_is_not_a_change() {
+        assert!(changes("a*(b+c)", "a * (b + c)").is_empty());
+        assert!(changes("(a * b) + c", "a * b + c").is_empty());
+    }
+
+    #[test]
+    fn test_diff_reports_semantic_changes() {
+        assert_eq!(
+            changes("net * 0.19", "net * 0.21"),
+            ["changed the constant 0.19 to 0.21"]
+        );
+        assert_eq!(
+            changes("price * qty + qty", "price * quantity + quantity"),
+            ["renamed the variable 'qty' to 'quantity'"]
+        );
+        assert_eq!(
+            changes("age >= 18", "age >= 18 and country == \"NL\""),
+            ["added 'and country == \"NL\"'"]
+        );
+        assert_eq!(
+            changes("age >= 65", "age >= 67 and member"),
+            ["changed the constant 65 to 67", "added 'and member'"]
+        );
+        assert_eq!(changes("a > 1 or b > 2", "a > 1"), ["removed 'or b > 2'"]);
+        assert_eq!(changes("total + 5 + 7", "total + 5"), ["removed '+ 7'"]);
+        assert_eq!(
+            changes("age >= 18 and member", "age > 21 and member"),
+            [
+                "changed the operator '>=' to '>'",
+                "changed the constant 18 to 21"
+            ]
+        );
+        assert_eq!(
+            changes("customer.name ?? \"\"", "customer.title ?? \"\""),
+            ["changed the field 'name' to 'title'"]
+        );
+        assert_eq!(
+            changes("customer.name ?? \"\"", "customer.name"),
+            ["removed '?? \"\"'"]
+        );
+    }
+
+    #[test]
+    fn test_diff_reports_the_operator_of_operands() {
+        assert_eq!(changes("price", "price * 0"), ["multiplied by '0'"]);
+        assert_eq!(changes("price", "0 - price"), ["subtracted from '0'"]);
+        assert_eq!(changes("price", "price - 0"), ["subtracted '0'"]);
+        assert_eq!(changes("price", "member or price"), ["added 'member or'"]);
+        assert_eq!(
+            changes("price / 2", "price"),
+            ["removed the division by '2'"]
+        );
+    }
+
+    #[test]
+    fn test_diff_renames_only_all_occurrences() {
+        assert_eq!(
+            changes("price * qty + price", "price * qty + cost"),
+            ["replaced 'price' by 'cost'"]
+        );
+        assert_eq!(
+            changes("price * qty", "qty * qty"),
+            ["replaced 'price' by 'qty'"]
+        );
+        assert_eq!(
+            changes("map(xs, x -> x * y)", "map(xs, y -> y * y)"),
+            ["replaced 'x -> x * y' by 'y -> y * y'"]
+        );
+        assert_eq!(
+            changes("map(xs, x -> x * y)", "map(xs, z -> z * y)"),
+            ["renamed the variable 'x' to 'z'"]
+        );
+    }
+
+    #[test]
+    fn test_diff_lists_and_calls() {
+        assert_eq!(
+            changes("max(a, b, c)", "min(a, c, d)"),
+            [
+                "replaced the function 'max' by 'min'",
+                "removed 'b'",
+                "added 'd'"
+            ]
+        );
+        assert_eq!(
+            changes("[1, 2, 3]", "[1, 5, 3, 4]"),
+            ["changed the constant 2 to 5", "added '4'"]
+        );
+        assert_eq!(
+            changes("map(xs, x -> x * 2)", "map(xs, item -> item * 3)"),
+            [
+                "renamed the variable 'x' to 'item'",
+                "changed the constant 2 to 3"
+            ]
+        );
+        assert_eq!(
+            changes("not paid", "paid is null"),
+            ["replaced 'not paid' by 'paid is null'"]
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use expressive_data::error::DataError;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -8,6 +9,24 @@ pub enum ExpressionError {
     EvaluationError { expression: String },
     #[error("the variable with name '{name:?}' is undefined")]
     UndefinedVariable { name: String },
+    #[error("the function with name '{name:?}' is undefined")]
+    UndefinedFunction { name: String },
+    #[error("invalid arguments for function '{function:}': {message:}")]
+    InvalidArguments { function: String, message: String },
+    #[error("the field '{path:}' is undefined")]
+    UndefinedField { path: String },
+    #[error("cannot access '{path:}' on a value of type '{type_name:}'")]
+    InvalidAccess { path: String, type_name: String },
+    #[error("the index {index:} is out of bounds for a list of length {length:}")]
+    IndexOutOfBounds { index: String, length: usize },
+    #[error("the variables must be a JSON object instead got '{type_name:}'")]
+    InvalidDocument { type_name: String },
+    #[error("the type '{name:}' is unknown")]
+    UnknownType { name: String },
+    #[error("type mismatch in '{expression:}': {message:}")]
+    TypeMismatch { expression: String, message: String },
     #[error("unexpected abstract syntax tree structure")]
     UnexpectedAbstractSyntaxTree,
+    #[error(transparent)]
+    DataError(#[from] DataError),
 }
@@ -1,163 +1,825 @@
+use crate::ast::{Accessor, BinaryOperator, Expression, PostfixOperator, PrefixOperator};
 use crate::error::ExpressionError;
-use crate::parser::{ExpressionParser, Rule};
-use expressive_data::numeric::NumericValue;
-use pest::iterators::{Pair, Pairs};
-use pest::Parser;
+use crate::function::{call, is_builtin, Argument, Lambda};
+use expressive_data::error::DataError;
+use expressive_data::value::Value;
+use std::cmp::Ordering;
 use std::collections::HashMap;
 
-pub fn evaluate(input: &str, variables: &HashMap<String, Value>) -> Result<Value, ExpressionError> {
-    let result = ExpressionParser::parse(Rule::input, input);
+/// Decides what happens when an expression refers to a variable or record field that was not
+/// provided.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Strictness {
+    /// Undefined variables are an `UndefinedVariable` error and missing fields an `UndefinedField`
+    /// error.
+    #[default]
+    Strict,
+    /// Undefined variables and missing fields evaluate to null.
+    Lenient,
+}
 
-    match result {
-        Ok(pairs) => resolve(pairs, variables),
-        Err(error) => Err(ExpressionError::ParseError {
-            message: format!("{}", error),
-        }),
+#[derive(Debug, Clone, Default)]
+pub struct EvaluationOptions {
+    pub strictness: Strictness,
+}
+
+// A variable scope. Lambdas evaluate their body in a scope with their parameters that extends the
+// scope they are defined in.
+struct Context<'a> {
+    variables: &'a HashMap<String, Value>,
+    options: &'a EvaluationOptions,
+    parent: Option<&'a Context<'a>>,
+}
+
+impl<'a> Context<'a> {
+    fn extend(&'a self, variables: &'a HashMap<String, Value>) -> Context<'a> {
+        Context {
+            variables,
+            options: self.options,
+            parent: Some(self),
+        }
     }
+
+    fn find(&self, name: &str) -> Option<&Value> {
+        self.variables
+            .get(name)
+            .or_else(|| self.parent.and_then(|parent| parent.find(name)))
+    }
+
+    fn lookup(&self, name: &str) -> Result<Value, ExpressionError> {
+        match (self.find(name), self.options.strictness) {
+            (Some(value), _) => Ok(value.clone()),
+            (None, Strictness::Lenient) => Ok(Value::Null),
+            (None, Strictness::Strict) => Err(ExpressionError::UndefinedVariable {
+                name: name.to_owned(),
+            }),
+        }
+    }
+}
+
+/// Whether a function is one of the built-in functions, which user-defined functions cannot
+/// replace.
+pub fn is_builtin_function(name: &str) -> bool {
+    is_builtin(name)
+}
+
+pub fn evaluate(input: &str, variables: &HashMap<String, Value>) -> Result<Value, ExpressionError> {
+    evaluate_with_options(input, variables, &EvaluationOptions::default())
 }
 
-fn resolve(
-    mut pairs: Pairs<Rule>,
+pub fn evaluate_with_options(
+    input: &str,
     variables: &HashMap<String, Value>,
+    options: &EvaluationOptions,
 ) -> Result<Value, ExpressionError> {
-    let expression_pair = pairs
-        .next()
-        .ok_or(ExpressionError::UnexpectedAbstractSyntaxTree)?;
-    resolve_branch(expression_pair, variables)
+    let expression: Expression = input.parse()?;
+    evaluate_expression(&expression, variables, options)
 }
 
-fn resolve_branch(
-    pair: Pair<Rule>,
-    variables: &HashMap<String, Value>,
+/// Evaluates an expression with the fields of a JSON object as its variables, so that paths such as
+/// `order.lines[0].price` resolve in the document.
+#[cfg(feature = "json")]
+pub fn evaluate_json(input: &str, document: &serde_json::Value) -> Result<Value, ExpressionError> {
+    evaluate_json_with_options(input, document, &EvaluationOptions::default())
+}
+
+#[cfg(feature = "json")]
+pub fn evaluate_json_with_options(
+    input: &str,
+    document: &serde_json::Value,
+    options: &EvaluationOptions,
 ) -> Result<Value, ExpressionError> {
-    match pair.as_rule() {
-        Rule::integer => {
-            let value = pair.as_str();
-            Ok(NumericValue::)
-        }
-        Rule::decimal => {
-            let val = pair.as_str();
-            let n = val.parse::<f32>().unwrap_or_else(|_| {
-                panic!("expected an decimal value to parse instead got: {}", val)
-            });
-            Ok(Value::FastDecimal(n))
-        }
-        Rule::variable_name => {
-            let name = pair.as_str();
-            variables
-                .get(name)
-                .cloned()
-                .ok_or(ExpressionError::UndefinedVariable {
-                    name: name.to_owned(),
-                })
-        }
-        Rule::parenthesized => {
-            let expression_pair = pair
-                .into_inner()
-                .next()
-                .ok_or(ExpressionError::UnexpectedAbstractSyntaxTree)?;
-            resolve_branch(expression_pair, variables)
+    let variables: HashMap<String, Value> = match document {
+        serde_json::Value::Object(fields) => fields
+            .iter()
+            .map(|(name, value)| (name.clone(), Value::from(value.clone())))
+            .collect(),
+        _ => {
+            return Err(ExpressionError::InvalidDocument {
+                type_name: Value::from(document.clone()).type_name().to_owned(),
+            })
         }
-        Rule::expression => {
-            let pairs: Vec<Pair<Rule>> = pair.into_inner().collect();
-            resolve_terminals(&pairs, variables)
-        }
-        _ => Err(ExpressionError::UnexpectedAbstractSyntaxTree),
-    }
+    };
+    evaluate_with_options(input, &variables, options)
 }
 
-fn resolve_terminals(
-    pairs: &[Pair<Rule>],
+/// Evaluates an expression that was parsed before, e.g. to evaluate it for many sets of variables.
+pub fn evaluate_expression(
+    expression: &Expression,
     variables: &HashMap<String, Value>,
+    options: &EvaluationOptions,
 ) -> Result<Value, ExpressionError> {
-    let mut operand_stack: Vec<Value> = Vec::new();
-    let mut operator_stack: Vec<Pair<Rule>> = Vec::new();
-
-    // Operands will be pairs on uneven indices and operators on even.
-    for (index, pair) in pairs.iter().enumerate() {
-        if index % 2 == 0 {
-            let operand_value = resolve_branch(pair.clone(), variables)?;
-            operand_stack.push(operand_value);
-        } else {
-            while let Some(top_op) = operator_stack.last() {
-                if get_precedence(top_op) >= get_precedence(pair) {
-                    let op = operator_stack.pop().unwrap();
-                    let right = operand_stack
-                        .pop()
-                        .ok_or(ExpressionError::UnexpectedAbstractSyntaxTree)?;
-                    let left = operand_stack
-                        .pop()
-                        .ok_or(ExpressionError::UnexpectedAbstractSyntaxTree)?;
-                    let result = apply_operator(left, op, right)?;
-                    operand_stack.push(result);
-                } else {
-                    break;
+    let context = Context {
+        variables,
+        options,
+        parent: None,
+    };
+    resolve(expression, &context)
+}
+
+fn resolve(expression: &Expression, context: &Context) -> Result<Value, ExpressionError> {
+    match expression {
+        Expression::Literal { value } => Ok(value.clone()),
+        Expression::Variable { name } => context.lookup(name),
+        Expression::List { items } => {
+            let items = items
+                .iter()
+                .map(|item| resolve(item, context))
+                .collect::<Result<Vec<Value>, ExpressionError>>()?;
+            Ok(Value::List(items))
+        }
+        Expression::FunctionCall { name, arguments } => {
+            let arguments = arguments
+                .iter()
+                .map(|argument| match argument {
+                    Expression::Lambda { parameters, body } => {
+                        Ok(Argument::Lambda(resolve_lambda(parameters, body, context)))
+                    }
+                    _ => Ok(Argument::Value(resolve(argument, context)?)),
+                })
+                .collect::<Result<Vec<Argument>, ExpressionError>>()?;
+            call(name, arguments)
+        }
+        Expression::Lambda { .. } => Err(ExpressionError::UnexpectedAbstractSyntaxTree),
+        Expression::Access { target, accessor } => {
+            let value = resolve(target, context)?;
+            let (key, path) = match accessor {
+                Accessor::Field(name) => {
+                    (Value::String(name.clone()), format!("{}.{}", target, name))
                 }
+                Accessor::Index(index) => match resolve(index, context)? {
+                    Value::String(key) => {
+                        let path = format!("{}[\"{}\"]", target, key);
+                        (Value::String(key), path)
+                    }
+                    key => {
+                        let path = format!("{}[{}]", target, key);
+                        (key, path)
+                    }
+                },
+            };
+            access_element(value, key, &path, context)
+        }
+        Expression::Binary {
+            operator,
+            left,
+            right,
+        } => {
+            let left_operand = match (operator, resolve(left, context)) {
+                // Coalescing is how a strict expression falls back for a missing variable or field,
+                // but only when the left operand is that variable or field itself.
+                (
+                    BinaryOperator::Coalesce,
+                    Err(
+                        ExpressionError::UndefinedVariable { .. }
+                        | ExpressionError::UndefinedField { .. },
+                    ),
+                ) if is_field_path(left) => Value::Null,
+                (_, left_operand) => left_operand?,
+            };
+            // The right operand is only evaluated when the left operand does not decide the result.
+            match (operator, &left_operand) {
+                (BinaryOperator::Coalesce, left_operand) if !left_operand.is_null() => {
+                    return Ok(left_operand.clone())
+                }
+                (BinaryOperator::And, Value::Boolean(false)) => return Ok(Value::Boolean(false)),
+                (BinaryOperator::Or, Value::Boolean(true)) => return Ok(Value::Boolean(true)),
+                _ => {}
             }
-            operator_stack.push(pair.clone());
+            let right_operand = resolve(right, context)?;
+            apply_operator(left_operand, *operator, right_operand)
+        }
+        Expression::Prefix { operator, operand } => {
+            let operand = resolve(operand, context)?;
+            apply_prefix_operator(operand, *operator)
+        }
+        Expression::Postfix { operator, operand } => {
+            let operand = resolve(operand, context)?;
+            Ok(apply_postfix_operator(operand, *operator))
         }
     }
+}
 
-    while let Some(op) = operator_stack.pop() {
-        let right = operand_stack
-            .pop()
-            .ok_or(ExpressionError::UnexpectedAbstractSyntaxTree)?;
-        let left = operand_stack
-            .pop()
-            .ok_or(ExpressionError::UnexpectedAbstractSyntaxTree)?;
-        let result = apply_operator(left, op, right)?;
-        operand_stack.push(result);
+// Whether an expression is a variable or a chain of fields of one, e.g. `customer.address.zip`.
+fn is_field_path(expression: &Expression) -> bool {
+    match expression {
+        Expression::Variable { .. } => true,
+        Expression::Access {
+            target,
+            accessor: Accessor::Field(_),
+        } => is_field_path(target),
+        _ => false,
     }
+}
 
-    if operand_stack.len() == 1 {
-        Ok(operand_stack.pop().unwrap())
-    } else {
-        Err(ExpressionError::UnexpectedAbstractSyntaxTree)
-    }
+fn resolve_lambda<'a>(
+    parameters: &'a [String],
+    body: &'a Expression,
+    context: &'a Context,
+) -> Box<Lambda<'a>> {
+    Box::new(move |arguments: Vec<Value>| {
+        if arguments.len() != parameters.len() {
+            return Err(ExpressionError::InvalidArguments {
+                function: format!("lambda ({})", parameters.join(", ")),
+                message: format!(
+                    "expected {} argument(s) but got {}",
+                    parameters.len(),
+                    arguments.len()
+                ),
+            });
+        }
+        let variables: HashMap<String, Value> = parameters.iter().cloned().zip(arguments).collect();
+        resolve(body, &context.extend(&variables))
+    })
 }
 
-fn get_precedence(operator: &Pair<Rule>) -> u32 {
-    match operator.as_rule() {
-        Rule::multiplication | Rule::division => 2,
-        Rule::addition | Rule::subtraction => 1,
-        _ => 0,
+fn access_element(
+    value: Value,
+    index: Value,
+    path: &str,
+    context: &Context,
+) -> Result<Value, ExpressionError> {
+    match (value, index) {
+        (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
+        (Value::Map(mut fields), Value::String(name)) => {
+            match (fields.remove(&name), context.options.strictness) {
+                (Some(value), _) => Ok(value),
+                (None, Strictness::Lenient) => Ok(Value::Null),
+                (None, Strictness::Strict) => Err(ExpressionError::UndefinedField {
+                    path: path.to_owned(),
+                }),
+            }
+        }
+        (Value::List(mut items), Value::Integer(index)) => {
+            let length = items.len();
+            let position = i64::try_from(&index)
+                .ok()
+                .map(|i| if i < 0 { i + length as i64 } else { i })
+                .filter(|i| (0..length as i64).contains(i))
+                .ok_or(ExpressionError::IndexOutOfBounds {
+                    index: index.to_string(),
+                    length,
+                })?;
+            Ok(items.swap_remove(position as usize))
+        }
+        (value, _) => Err(ExpressionError::InvalidAccess {
+            path: path.to_owned(),
+            type_name: value.type_name().to_owned(),
+        }),
     }
 }
 
 fn apply_operator(
     left_operand: Value,
-    operator: Pair<Rule>,
+    operator: BinaryOperator,
+    right_operand: Value,
+) -> Result<Value, ExpressionError> {
+    match operator {
+        BinaryOperator::Add => Ok((left_operand + right_operand)?),
+        BinaryOperator::Subtract => Ok((left_operand - right_operand)?),
+        BinaryOperator::Multiply => Ok((left_operand * right_operand)?),
+        BinaryOperator::Divide => Ok((left_operand / right_operand)?),
+        BinaryOperator::Power => Ok(left_operand.power(right_operand)?),
+        BinaryOperator::And => Ok(left_operand.and(right_operand)?),
+        BinaryOperator::Or => Ok(left_operand.or(right_operand)?),
+        BinaryOperator::Coalesce => Ok(left_operand.coalesce(right_operand)),
+        BinaryOperator::In => Ok(left_operand.is_in(right_operand)?),
+        BinaryOperator::Equal => Ok(compare(left_operand, right_operand, |o| o.is_eq())?),
+        BinaryOperator::NotEqual => Ok(compare(left_operand, right_operand, |o| o.is_ne())?),
+        BinaryOperator::Less => Ok(compare(left_operand, right_operand, |o| o.is_lt())?),
+        BinaryOperator::LessOrEqual => Ok(compare(left_operand, right_operand, |o| o.is_le())?),
+        BinaryOperator::Greater => Ok(compare(left_operand, right_operand, |o| o.is_gt())?),
+        BinaryOperator::GreaterOrEqual => Ok(compare(left_operand, right_operand, |o| o.is_ge())?),
+    }
+}
+
+// Comparing with null is unknown and results in null, like it does in SQL. Values that cannot be
+// ordered can still be tested for (in)equality.
+fn compare(
+    left_operand: Value,
     right_operand: Value,
+    accept: fn(Ordering) -> bool,
+) -> Result<Value, DataError> {
+    if left_operand.is_null() || right_operand.is_null() {
+        return Ok(Value::Null);
+    }
+    match left_operand.compare(&right_operand) {
+        Ok(ordering) => Ok(Value::Boolean(accept(ordering))),
+        Err(_) if accept(Ordering::Less) == accept(Ordering::Greater) => {
+            let equal = left_operand == right_operand;
+            Ok(Value::Boolean(accept(Ordering::Equal) == equal))
+        }
+        Err(error) => Err(error),
+    }
+}
+
+fn apply_prefix_operator(
+    operand: Value,
+    operator: PrefixOperator,
 ) -> Result<Value, ExpressionError> {
-    match operator.as_rule() {
-        Rule::addition => Ok(left_operand + right_operand),
-        Rule::subtraction => Ok(left_operand - right_operand),
-        Rule::multiplication => Ok(left_operand * right_operand),
-        Rule::division => Ok(left_operand / right_operand),
-        _ => Err(ExpressionError::UnexpectedAbstractSyntaxTree),
+    match operator {
+        PrefixOperator::Not => Ok((!operand)?),
+    }
+}
+
+fn apply_postfix_operator(operand: Value, operator: PostfixOperator) -> Value {
+    match operator {
+        PostfixOperator::IsNull => Value::Boolean(operand.is_null()),
+        PostfixOperator::IsNotNull => Value::Boolean(!operand.is_null()),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use bigdecimal::num_bigint::BigInt;
     use bigdecimal::BigDecimal;
+    use expressive_data::money::{Currency, Money};
+    use expressive_data::numeric::NumericValue;
+    use expressive_data::unit::{Quantity, UnitRegistry};
+    use std::collections::BTreeMap;
+    use std::str::FromStr;
+
+    fn money(amount: &str, code: &str) -> Value {
+        Money::new(
+            BigDecimal::from_str(amount).unwrap(),
+            Currency::from_code(code).unwrap(),
+        )
+        .into()
+    }
 
     #[test]
     fn test_evaluate() {
         let expression = "a / b";
         let mut variables = HashMap::new();
-        variables.insert("a".to_string(), Value::FastInteger(3));
+        variables.insert("a".to_string(), Value::Integer(BigInt::from(3)));
         variables.insert(
             "b".to_string(),
-            Value::PreciseDecimal(BigDecimal::try_from(1.5).unwrap()),
+            Value::Decimal(BigDecimal::try_from(1.5).unwrap()),
         );
         let result = evaluate(expression, &variables);
         assert!(result.is_ok(), "result = {:?}", result);
         let value = result.unwrap();
+        assert_eq!(value, Value::Decimal(BigDecimal::from(2)));
+    }
+
+    #[test]
+    fn test_evaluate_money() {
+        let mut variables = HashMap::new();
+        variables.insert("quantity".to_string(), Value::Integer(BigInt::from(3)));
+        let result = evaluate("EUR 12.5 * quantity + EUR 0.99", &variables).unwrap();
+        assert_eq!(result, money("38.49", "EUR"));
+        assert_eq!(result.to_string(), "EUR 38.49");
+    }
+
+    #[test]
+    fn test_evaluate_mixed_currencies() {
+        let result = evaluate("EUR 1 + USD 1", &HashMap::new());
+        assert!(matches!(
+            result,
+            Err(ExpressionError::DataError(
+                DataError::CurrencyMismatch { .. }
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_evaluate_quantities() {
+        let mut variables = HashMap::new();
+        variables.insert(
+            "weight".to_string(),
+            Quantity::new(
+                NumericValue::try_from("2.5").unwrap(),
+                UnitRegistry::standard().parse("kg").unwrap(),
+            )
+            .into(),
+        );
+        let result = evaluate("weight + 500 g", &variables).unwrap();
+        assert_eq!(result.to_string(), "3.0 kg");
+        let result = evaluate("120 km / 90 min", &variables).unwrap();
+        let unit = UnitRegistry::standard().parse("km/min").unwrap();
+        assert!(matches!(result, Value::Quantity { unit: u, .. } if u == unit));
+        let result = evaluate("3 km / 500 m", &variables).unwrap();
+        assert_eq!(result, Value::Decimal(BigDecimal::from(6)));
+    }
+
+    #[test]
+    fn test_evaluate_dimension_mismatch() {
+        let result = evaluate("5 kg + 2 m", &HashMap::new());
+        assert!(matches!(
+            result,
+            Err(ExpressionError::DataError(
+                DataError::DimensionMismatch { .. }
+            ))
+        ));
+        let result = evaluate("5 kg + 2", &HashMap::new());
+        assert!(matches!(
+            result,
+            Err(ExpressionError::DataError(
+                DataError::DimensionMismatch { .. }
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_evaluate_null_propagation() {
+        let mut variables = HashMap::new();
+        variables.insert("discount".to_string(), Value::Null);
+        variables.insert("price".to_string(), Value::Integer(BigInt::from(10)));
+        let result = evaluate("price - discount", &variables).unwrap();
+        assert_eq!(result, Value::Null);
+        let result = evaluate("price - (discount ?? 2)", &variables).unwrap();
+        assert_eq!(result, Value::Integer(BigInt::from(8)));
+        let result = evaluate("price + discount is null", &variables).unwrap();
+        assert_eq!(result, Value::Boolean(true));
+        let result = evaluate("discount is not null", &variables).unwrap();
+        assert_eq!(result, Value::Boolean(false));
+    }
+
+    #[test]
+    fn test_evaluate_three_valued_logic() {
+        let variables = HashMap::new();
+        let cases = [
+            ("true and null", Value::Null),
+            ("false and null", Value::Boolean(false)),
+            ("null or true", Value::Boolean(true)),
+            ("null or false", Value::Null),
+            ("true or false and null", Value::Boolean(true)),
+            ("true and false", Value::Boolean(false)),
+            ("null and false", Value::Boolean(false)),
+            ("false or true", Value::Boolean(true)),
+            ("false or false", Value::Boolean(false)),
+        ];
+        for (expression, expected) in cases {
+            let result = evaluate(expression, &variables).unwrap();
+            assert_eq!(result, expected, "expression = {}", expression);
+        }
+    }
+
+    #[test]
+    fn test_evaluate_short_circuit() {
+        let variables = HashMap::new();
+        let cases = [
+            ("1 ?? (1 / 0)", Value::Integer(BigInt::from(1))),
+            ("false and 1 / 0 > 1", Value::Boolean(false)),
+            ("true or 1 / 0 > 1", Value::Boolean(true)),
+            ("false and missing", Value::Boolean(false)),
+        ];
+        for (expression, expected) in cases {
+            let result = evaluate(expression, &variables).unwrap();
+            assert_eq!(result, expected, "expression = {}", expression);
+        }
+        let result = evaluate("null ?? (1 / 0)", &variables);
+        assert!(matches!(
+            result,
+            Err(ExpressionError::DataError(DataError::DivisionByZero))
+        ));
+    }
+
+    #[test]
+    fn test_evaluate_strictness() {
+        let variables = HashMap::new();
+        let result = evaluate("missing + 1", &variables);
+        assert!(matches!(
+            result,
+            Err(ExpressionError::UndefinedVariable { .. })
+        ));
+        let result = evaluate("missing ?? 1", &variables).unwrap();
+        assert_eq!(result, Value::Integer(BigInt::from(1)));
+        // Only a missing operand itself falls back, not a typo inside it.
+        let variables = HashMap::from([("a".to_string(), Value::Integer(BigInt::from(2)))]);
+        let strict = EvaluationOptions {
+            strictness: Strictness::Strict,
+        };
+        let result = evaluate_with_options("(a * typo) ?? 0", &variables, &strict);
+        assert!(matches!(
+            result,
+            Err(ExpressionError::UndefinedVariable { .. })
+        ));
+        let result = evaluate("sum([a, typo]) ?? 0", &variables);
+        assert!(matches!(
+            result,
+            Err(ExpressionError::UndefinedVariable { .. })
+        ));
+        let variables = HashMap::new();
+        let options = EvaluationOptions {
+            strictness: Strictness::Lenient,
+        };
+        let result = evaluate_with_options("missing ?? 1", &variables, &options).unwrap();
+        assert_eq!(result, Value::Integer(BigInt::from(1)));
+    }
+
+    #[test]
+    fn test_evaluate_lists() {
+        let mut variables = HashMap::new();
+        variables.insert(
+            "quantities".to_string(),
+            Value::List(vec![
+                Value::Integer(BigInt::from(4)),
+                Value::Integer(BigInt::from(2)),
+                Value::Null,
+            ]),
+        );
+        let result = evaluate("quantities[0] + quantities[-2]", &variables).unwrap();
+        assert_eq!(result, Value::Integer(BigInt::from(6)));
+        let result = evaluate("[1, 2, 3][1] * 2", &variables).unwrap();
+        assert_eq!(result, Value::Integer(BigInt::from(4)));
+        let result = evaluate("quantities[3] + 1", &variables);
+        assert!(matches!(
+            result,
+            Err(ExpressionError::IndexOutOfBounds { .. })
+        ));
+    }
+
+    #[test]
+    fn test_evaluate_membership() {
+        let variables = HashMap::new();
+        let cases = [
+            ("2 in [1, 2]", Value::Boolean(true)),
+            ("3 in [1, 2]", Value::Boolean(false)),
+            ("3 in [1, null]", Value::Null),
+            ("\"NL\" in [\"NL\", \"BE\"] and true", Value::Boolean(true)),
+        ];
+        for (expression, expected) in cases {
+            let result = evaluate(expression, &variables).unwrap();
+            assert_eq!(result, expected, "expression = {}", expression);
+        }
+    }
+
+    #[test]
+    fn test_evaluate_record_fields() {
+        let address = BTreeMap::from([("country".to_string(), Value::String("NL".into()))]);
+        let customer = BTreeMap::from([
+            ("address".to_string(), Value::Map(address)),
+            ("unit price".to_string(), Value::Integer(BigInt::from(5))),
+        ]);
+        let mut variables = HashMap::new();
+        variables.insert("customer".to_string(), Value::Map(customer));
+
+        let result = evaluate("customer.address.country in [\"NL\"]", &variables).unwrap();
+        assert_eq!(result, Value::Boolean(true));
+        let result = evaluate("customer[\"unit price\"] * 2", &variables).unwrap();
+        assert_eq!(result, Value::Integer(BigInt::from(10)));
+
+        let result = evaluate("customer.address.city is null", &variables);
+        match result {
+            Err(ExpressionError::UndefinedField { path }) => {
+                assert_eq!(path, "customer.address.city")
+            }
+            other => panic!("expected an undefined field error, got {:?}", other),
+        }
+        let options = EvaluationOptions {
+            strictness: Strictness::Lenient,
+        };
+        let result =
+            evaluate_with_options("customer.address.city is null", &variables, &options).unwrap();
+        assert_eq!(result, Value::Boolean(true));
+        variables.insert("x".to_string(), Value::Integer(BigInt::from(5)));
+        let result = evaluate("x.y", &variables).unwrap_err();
+        assert_eq!(
+            result.to_string(),
+            "cannot access 'x.y' on a value of type 'integer'"
+        );
+    }
+
+    #[test]
+    fn test_evaluate_comparisons() {
+        let variables = HashMap::new();
+        let cases = [
+            ("2 == 2.0", Value::Boolean(true)),
+            ("1 != 2", Value::Boolean(true)),
+            ("EUR 10 >= EUR 9.99", Value::Boolean(true)),
+            ("1 km > 999 m", Value::Boolean(true)),
+            ("\"a\" < \"b\"", Value::Boolean(true)),
+            ("[1] == [1]", Value::Boolean(true)),
+            ("\"a\" == 1", Value::Boolean(false)),
+            ("null == null", Value::Null),
+            ("1 + 1 <= 1 or false", Value::Boolean(false)),
+        ];
+        for (expression, expected) in cases {
+            let result = evaluate(expression, &variables).unwrap();
+            assert_eq!(result, expected, "expression = {}", expression);
+        }
+        assert!(evaluate("\"a\" < 1", &variables).is_err());
+    }
+
+    #[test]
+    fn test_evaluate_lambdas() {
+        let line = |price: i64, qty: i64| {
+            Value::Map(BTreeMap::from([
+                ("price".to_string(), Value::Integer(BigInt::from(price))),
+                ("qty".to_string(), Value::Integer(BigInt::from(qty))),
+            ]))
+        };
+        let mut variables = HashMap::new();
+        variables.insert(
+            "lines".to_string(),
+            Value::List(vec![line(10, 2), line(5, 1), line(20, 3)]),
+        );
+        variables.insert("discount".to_string(), Value::Integer(BigInt::from(1)));
+
+        let result = evaluate(
+            "sum(map(lines, x -> (x.price - discount) * x.qty))",
+            &variables,
+        )
+        .unwrap();
+        assert_eq!(result, Value::Integer(BigInt::from(79)));
+        let result = evaluate(
+            "reduce(filter(lines, x -> x.qty > 1), (total, x) -> total + x.qty, 0)",
+            &variables,
+        )
+        .unwrap();
+        assert_eq!(result, Value::Integer(BigInt::from(5)));
+        let result = evaluate("count(lines) ?? map(lines, x -> x.unknown)", &variables).unwrap();
+        assert_eq!(result, evaluate("count(lines)", &variables).unwrap());
+        let result = evaluate("count(map(lines, x -> x.unknown))", &variables);
+        assert!(matches!(
+            result,
+            Err(ExpressionError::UndefinedField { .. })
+        ));
+    }
+
+    #[test]
+    fn test_evaluate_lambda_scoping() {
+        let mut variables = HashMap::new();
+        variables.insert("x".to_string(), Value::Integer(BigInt::from(100)));
+        variables.insert(
+            "xs".to_string(),
+            Value::List(vec![Value::Integer(BigInt::from(1))]),
+        );
+        let result = evaluate("map(xs, x -> x + 1)[0] + x", &variables).unwrap();
+        assert_eq!(result, Value::Integer(BigInt::from(102)));
+        let result = evaluate("map(xs, y -> y + x)[0]", &variables).unwrap();
+        assert_eq!(result, Value::Integer(BigInt::from(101)));
+        let result = evaluate("map(xs, y -> y)[0] + y", &variables);
+        assert!(matches!(
+            result,
+            Err(ExpressionError::UndefinedVariable { .. })
+        ));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_evaluate_json() {
+        let document: serde_json::Value = serde_json::from_str(
+            r#"{"order": {"lines": [{"price": 12345678901234567890.10, "qty": 2}], "vip": true}}"#,
+        )
+        .unwrap();
+        let result = evaluate_json("order.lines[0].price * order.lines[0].qty", &document);
+        assert_eq!(
+            result.unwrap(),
+            Value::Decimal(BigDecimal::from_str("24691357802469135780.20").unwrap())
+        );
+        let result = evaluate_json("order.vip and order.lines[0].qty > 1", &document);
+        assert_eq!(result.unwrap(), Value::Boolean(true));
+        let result = evaluate_json("order.missing ?? 0", &document);
+        assert_eq!(result.unwrap(), Value::from(0i64));
+        let result = evaluate_json("order.missing + 0", &document);
+        assert!(matches!(
+            result,
+            Err(ExpressionError::UndefinedField { .. })
+        ));
+        let result = evaluate_json("a + 1", &serde_json::json!([1]));
+        assert!(matches!(
+            result,
+            Err(ExpressionError::InvalidDocument { .. })
+        ));
+    }
+
+    #[test]
+    fn test_evaluate_derived_context() {
+        use expressive_data::context::{ExpressiveContext, ToValue};
+
+        #[derive(ExpressiveContext)]
+        struct Address {
+            country: String,
+        }
+
+        #[derive(ExpressiveContext)]
+        struct Customer {
+            #[expressive(rename = "customer_name")]
+            name: String,
+            address: Option<Address>,
+            discounts: Vec<u32>,
+            #[expressive(skip)]
+            #[allow(dead_code)]
+            password: String,
+        }
+
+        let customer = Customer {
+            name: "Ada".into(),
+            address: Some(Address {
+                country: "NL".into(),
+            }),
+            discounts: vec![5, 10],
+            password: "secret".into(),
+        };
+        let variables = customer.variables();
+        let result = evaluate(
+            "address.country == \"NL\" and customer_name != \"\" and discounts[1] == 10",
+            &variables,
+        );
+        assert_eq!(result.unwrap(), Value::Boolean(true));
+        assert!(!variables.contains_key("password"));
+        let result = evaluate(
+            "address is null",
+            &Customer {
+                address: None,
+                ..customer
+            }
+            .variables(),
+        );
+        assert_eq!(result.unwrap(), Value::Boolean(true));
+        let value = Address {
+            country: "BE".into(),
+        }
+        .to_value();
+        assert_eq!(value.to_string(), "{country: BE}");
+    }
+
+    #[test]
+    fn test_evaluate_numeric_literals() {
+        let variables = HashMap::new();
+        let cases = [
+            ("1.5e-3", "0.0015"),
+            ("1_000_000", "1000000"),
+            ("0xFF + 0b1010", "265"),
+            (".5 + 1", "1.5"),
+            ("15% * 200", "30.00"),
+            ("2.5e2 - 1", "249"),
+            ("EUR 1_250.5", "EUR 1250.50"),
+            ("-0x10 + 1E1", "-6"),
+        ];
+        for (expression, expected) in cases {
+            let result = evaluate(expression, &variables).unwrap();
+            assert_eq!(result.to_string(), expected, "expression = {}", expression);
+        }
+        let result = evaluate("15%", &variables).unwrap();
         assert_eq!(
-            value,
-            Value::PreciseDecimal(BigDecimal::try_from(1.5).unwrap())
+            result,
+            Value::Decimal(BigDecimal::from_str("0.15").unwrap())
         );
+        assert!(evaluate("1__0 + 1", &variables).is_err());
+        assert!(evaluate("0x + 1", &variables).is_err());
+        for source in ["1e999999999", "2.5E-1001", "1e00000000000000000000001001"] {
+            assert!(
+                matches!(
+                    evaluate(source, &variables),
+                    Err(ExpressionError::ParseError { .. })
+                ),
+                "source = {}",
+                source
+            );
+        }
+        let result = evaluate("1e1000 / 1e1000", &variables).unwrap();
+        assert_eq!(result.to_string(), "1");
+    }
+
+    #[test]
+    fn test_evaluate_power_and_not() {
+        let mut variables = HashMap::new();
+        variables.insert("a".to_string(), Value::Boolean(false));
+        variables.insert("n".to_string(), Value::Null);
+        let cases = [
+            ("2 ^ 3 ^ 2", "512"),
+            ("(2 ^ 3) ^ 2", "64"),
+            ("2 * 3 ^ 2", "18"),
+            ("1.5 ^ 2", "2.25"),
+            ("2 ^ -2", "0.25"),
+            ("(3 m) ^ 2", "9 m^2"),
+            ("not a and true", "true"),
+            ("not 1 == 2", "true"),
+            ("not n", "null"),
+            ("not n is null", "false"),
+        ];
+        for (expression, expected) in cases {
+            let result = evaluate(expression, &variables).unwrap();
+            assert_eq!(result.to_string(), expected, "expression = {}", expression);
+        }
+        assert!(evaluate("2 ^ 0.5", &variables).is_err());
+        assert!(evaluate("not 1", &variables).is_err());
+    }
+
+    #[test]
+    fn test_evaluate_single_terminal() {
+        let mut variables = HashMap::new();
+        variables.insert("price".to_string(), Value::from(12_i64));
+        let cases = [
+            ("42", "42"),
+            ("price", "12"),
+            ("(price)", "12"),
+            ("EUR 12.50", "EUR 12.50"),
+            ("\"text\"", "text"),
+            ("[price, 1][0]", "12"),
+            ("null", "null"),
+        ];
+        for (expression, expected) in cases {
+            let result = evaluate(expression, &variables).unwrap();
+            assert_eq!(result.to_string(), expected, "expression = {}", expression);
+        }
+        assert!(matches!(
+            evaluate("discount", &variables),
+            Err(ExpressionError::UndefinedVariable { .. })
+        ));
     }
 }
@@ -0,0 +1,302 @@
+use crate::error::ExpressionError;
+use crate::function::{invalid_arguments, Argument, Lambda};
+use expressive_data::value::Value;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+type ListAndLambda<'a> = (Option<Vec<Value>>, Box<Lambda<'a>>, Vec<Argument<'a>>);
+
+// Higher-order functions take a list and a lambda that is called for every element. A null list
+// results in null.
+fn list_and_lambda<'a>(
+    function: &str,
+    arguments: Vec<Argument<'a>>,
+) -> Result<ListAndLambda<'a>, ExpressionError> {
+    let mut arguments = arguments.into_iter();
+    match (arguments.next(), arguments.next()) {
+        (Some(Argument::Value(Value::List(items))), Some(Argument::Lambda(lambda))) => {
+            Ok((Some(items), lambda, arguments.collect()))
+        }
+        (Some(Argument::Value(Value::Null)), Some(Argument::Lambda(lambda))) => {
+            Ok((None, lambda, arguments.collect()))
+        }
+        _ => Err(invalid_arguments(function, "expected a list and a lambda")),
+    }
+}
+
+fn no_more_arguments(function: &str, rest: Vec<Argument>) -> Result<(), ExpressionError> {
+    match rest.is_empty() {
+        true => Ok(()),
+        false => Err(invalid_arguments(function, "expected a list and a lambda")),
+    }
+}
+
+// A predicate must result in a boolean, where null means unknown.
+fn test(function: &str, lambda: &Lambda, item: Value) -> Result<Option<bool>, ExpressionError> {
+    match lambda(vec![item])? {
+        Value::Boolean(b) => Ok(Some(b)),
+        Value::Null => Ok(None),
+        _ => Err(invalid_arguments(
+            function,
+            "the lambda must result in a boolean",
+        )),
+    }
+}
+
+pub(super) fn map(arguments: Vec<Argument>) -> Result<Value, ExpressionError> {
+    let (items, lambda, rest) = list_and_lambda("map", arguments)?;
+    no_more_arguments("map", rest)?;
+    let Some(items) = items else {
+        return Ok(Value::Null);
+    };
+    let items = items
+        .into_iter()
+        .map(|item| lambda(vec![item]))
+        .collect::<Result<Vec<Value>, ExpressionError>>()?;
+    Ok(Value::List(items))
+}
+
+// Filtering keeps the elements for which the predicate is true, like a where clause in SQL.
+pub(super) fn filter(arguments: Vec<Argument>) -> Result<Value, ExpressionError> {
+    let (items, lambda, rest) = list_and_lambda("filter", arguments)?;
+    no_more_arguments("filter", rest)?;
+    let Some(items) = items else {
+        return Ok(Value::Null);
+    };
+    let mut result = Vec::new();
+    for item in items {
+        if test("filter", &lambda, item.clone())? == Some(true) {
+            result.push(item);
+        }
+    }
+    Ok(Value::List(result))
+}
+
+pub(super) fn find(arguments: Vec<Argument>) -> Result<Value, ExpressionError> {
+    let (items, lambda, rest) = list_and_lambda("find", arguments)?;
+    no_more_arguments("find", rest)?;
+    for item in items.into_iter().flatten() {
+        if test("find", &lambda, item.clone())? == Some(true) {
+            return Ok(item);
+        }
+    }
+    Ok(Value::Null)
+}
+
+// Any and all use three-valued logic: an unknown result for one of the elements makes the result
+// unknown unless another element decides it.
+pub(super) fn any(arguments: Vec<Argument>) -> Result<Value, ExpressionError> {
+    quantify("any", arguments, true)
+}
+
+pub(super) fn all(arguments: Vec<Argument>) -> Result<Value, ExpressionError> {
+    quantify("all", arguments, false)
+}
+
+fn quantify(
+    function: &str,
+    arguments: Vec<Argument>,
+    decisive: bool,
+) -> Result<Value, ExpressionError> {
+    let (items, lambda, rest) = list_and_lambda(function, arguments)?;
+    no_more_arguments(function, rest)?;
+    let Some(items) = items else {
+        return Ok(Value::Null);
+    };
+    let mut unknown = false;
+    for item in items {
+        match test(function, &lambda, item)? {
+            Some(b) if b == decisive => return Ok(Value::Boolean(decisive)),
+            Some(_) => {}
+            None => unknown = true,
+        }
+    }
+    match unknown {
+        true => Ok(Value::Null),
+        false => Ok(Value::Boolean(!decisive)),
+    }
+}
+
+// Reducing folds the elements into an accumulator, starting with the optional initial value or the
+// first element otherwise.
+pub(super) fn reduce(arguments: Vec<Argument>) -> Result<Value, ExpressionError> {
+    let (items, lambda, rest) = list_and_lambda("reduce", arguments)?;
+    let mut rest = rest.into_iter();
+    let initial = match (rest.next(), rest.next()) {
+        (None, None) => None,
+        (Some(Argument::Value(initial)), None) => Some(initial),
+        _ => {
+            return Err(invalid_arguments(
+                "reduce",
+                "expected a list, a lambda and an optional initial value",
+            ))
+        }
+    };
+    let Some(items) = items else {
+        return Ok(Value::Null);
+    };
+    let mut items = items.into_iter();
+    let Some(initial) = initial.or_else(|| items.next()) else {
+        return Ok(Value::Null);
+    };
+    items.try_fold(initial, |total, item| lambda(vec![total, item]))
+}
+
+// Sorting by a key is stable and puts elements with a null key last.
+pub(super) fn sort_by(arguments: Vec<Argument>) -> Result<Value, ExpressionError> {
+    let (items, lambda, rest) = list_and_lambda("sort_by", arguments)?;
+    no_more_arguments("sort_by", rest)?;
+    let Some(items) = items else {
+        return Ok(Value::Null);
+    };
+    let mut keyed = items
+        .into_iter()
+        .map(|item| Ok((lambda(vec![item.clone()])?, item)))
+        .collect::<Result<Vec<(Value, Value)>, ExpressionError>>()?;
+    let mut error = None;
+    keyed.sort_by(
+        |(left, _), (right, _)| match (left.is_null(), right.is_null()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            (false, false) => left.compare(right).unwrap_or_else(|e| {
+                error.get_or_insert(e);
+                Ordering::Equal
+            }),
+        },
+    );
+    match error {
+        Some(error) => Err(error.into()),
+        None => Ok(Value::List(
+            keyed.into_iter().map(|(_, item)| item).collect(),
+        )),
+    }
+}
+
+// Grouping results in a list of groups ordered by key, each a map with the `key` and the `items`
+// with that key. Keys keep their type, so the number 1 and the text "1" are different groups.
+pub(super) fn group_by(arguments: Vec<Argument>) -> Result<Value, ExpressionError> {
+    let (items, lambda, rest) = list_and_lambda("group_by", arguments)?;
+    no_more_arguments("group_by", rest)?;
+    let Some(items) = items else {
+        return Ok(Value::Null);
+    };
+    let mut groups: BTreeMap<Value, Vec<Value>> = BTreeMap::new();
+    for item in items {
+        let key = lambda(vec![item.clone()])?;
+        groups.entry(key).or_default().push(item);
+    }
+    Ok(Value::List(
+        groups
+            .into_iter()
+            .map(|(key, items)| {
+                Value::Map(BTreeMap::from([
+                    ("key".to_string(), key),
+                    ("items".to_string(), Value::List(items)),
+                ]))
+            })
+            .collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::ExpressionError;
+    use crate::function::{call, Argument, Lambda};
+    use bigdecimal::num_bigint::BigInt;
+    use expressive_data::value::Value;
+
+    fn integers(values: &[i64]) -> Argument<'static> {
+        Argument::Value(Value::List(
+            values
+                .iter()
+                .map(|value| Value::Integer(BigInt::from(*value)))
+                .collect(),
+        ))
+    }
+
+    fn lambda(f: fn(Vec<Value>) -> Value) -> Argument<'static> {
+        let lambda: Box<Lambda> = Box::new(move |arguments| Ok(f(arguments)));
+        Argument::Lambda(lambda)
+    }
+
+    fn is_even(arguments: Vec<Value>) -> Value {
+        match &arguments[0] {
+            Value::Integer(i) => Value::Boolean(i % 2 == BigInt::from(0)),
+            _ => Value::Null,
+        }
+    }
+
+    #[test]
+    fn test_map_filter_find() {
+        let double = lambda(|arguments| (arguments[0].clone() * Value::Integer(2.into())).unwrap());
+        let result = call("map", vec![integers(&[1, 2, 3]), double]).unwrap();
+        assert_eq!(result.to_string(), "[2, 4, 6]");
+        let result = call("filter", vec![integers(&[1, 2, 3, 4]), lambda(is_even)]).unwrap();
+        assert_eq!(result.to_string(), "[2, 4]");
+        let result = call("find", vec![integers(&[1, 3, 4, 6]), lambda(is_even)]).unwrap();
+        assert_eq!(result, Value::Integer(BigInt::from(4)));
+        let result = call("find", vec![integers(&[1, 3]), lambda(is_even)]).unwrap();
+        assert_eq!(result, Value::Null);
+    }
+
+    #[test]
+    fn test_any_and_all() {
+        let with_null = Argument::Value(Value::List(vec![Value::Integer(1.into()), Value::Null]));
+        let cases = [
+            ("any", integers(&[1, 2]), Value::Boolean(true)),
+            ("any", integers(&[1, 3]), Value::Boolean(false)),
+            ("all", integers(&[2, 4]), Value::Boolean(true)),
+            ("all", integers(&[]), Value::Boolean(true)),
+            ("any", with_null, Value::Null),
+        ];
+        for (function, list, expected) in cases {
+            let result = call(function, vec![list, lambda(is_even)]).unwrap();
+            assert_eq!(result, expected, "function = {}", function);
+        }
+    }
+
+    #[test]
+    fn test_reduce() {
+        let add = || lambda(|arguments| (arguments[0].clone() + arguments[1].clone()).unwrap());
+        let result = call("reduce", vec![integers(&[1, 2, 3]), add()]).unwrap();
+        assert_eq!(result, Value::Integer(BigInt::from(6)));
+        let initial = Argument::Value(Value::Integer(10.into()));
+        let result = call("reduce", vec![integers(&[1, 2, 3]), add(), initial]).unwrap();
+        assert_eq!(result, Value::Integer(BigInt::from(16)));
+        let result = call("reduce", vec![integers(&[]), add()]).unwrap();
+        assert_eq!(result, Value::Null);
+    }
+
+    #[test]
+    fn test_sort_by_and_group_by() {
+        let negate = lambda(|arguments| (Value::Integer(0.into()) - arguments[0].clone()).unwrap());
+        let result = call("sort_by", vec![integers(&[2, 3, 1]), negate]).unwrap();
+        assert_eq!(result.to_string(), "[3, 2, 1]");
+        let result = call("group_by", vec![integers(&[1, 2, 3, 4]), lambda(is_even)]).unwrap();
+        assert_eq!(
+            result.to_string(),
+            "[{items: [1, 3], key: false}, {items: [2, 4], key: true}]"
+        );
+        let mixed = Argument::Value(Value::List(vec![
+            Value::Integer(BigInt::from(1)),
+            Value::String("1".to_string()),
+            Value::Decimal("1.0".parse().unwrap()),
+        ]));
+        let identity = lambda(|arguments| arguments[0].clone());
+        let result = call("group_by", vec![mixed, identity]).unwrap();
+        assert_eq!(
+            result.to_string(),
+            "[{items: [1, 1.0], key: 1}, {items: [1], key: 1}]"
+        );
+    }
+
+    #[test]
+    fn test_requires_lambda() {
+        let result = call("map", vec![integers(&[1]), integers(&[1])]);
+        assert!(matches!(
+            result,
+            Err(ExpressionError::InvalidArguments { .. })
+        ));
+    }
+}
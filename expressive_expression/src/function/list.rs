@@ -0,0 +1,199 @@
+use crate::error::ExpressionError;
+use crate::function::invalid_arguments;
+use bigdecimal::num_bigint::BigInt;
+use expressive_data::value::Value;
+use std::cmp::Ordering;
+
+// Collection functions take either a single list or the values to work on as separate arguments,
+// where a single null is an empty collection.
+fn items(function: &str, arguments: Vec<Value>) -> Result<Vec<Value>, ExpressionError> {
+    match <[Value; 1]>::try_from(arguments) {
+        Ok([Value::List(items)]) => Ok(items),
+        Ok([Value::Null]) => Ok(Vec::new()),
+        Ok([value]) => Err(invalid_arguments(
+            function,
+            &format!(
+                "expected a list or more than one value instead got a single {}",
+                value.type_name()
+            ),
+        )),
+        Err(arguments) => Ok(arguments),
+    }
+}
+
+fn non_null_items(function: &str, arguments: Vec<Value>) -> Result<Vec<Value>, ExpressionError> {
+    Ok(items(function, arguments)?
+        .into_iter()
+        .filter(|item| !item.is_null())
+        .collect())
+}
+
+pub(super) fn count(arguments: Vec<Value>) -> Result<Value, ExpressionError> {
+    Ok(Value::Integer(BigInt::from(
+        items("count", arguments)?.len(),
+    )))
+}
+
+// Aggregates skip nulls and are null for an empty list, like they are in SQL.
+pub(super) fn sum(arguments: Vec<Value>) -> Result<Value, ExpressionError> {
+    let mut items = non_null_items("sum", arguments)?.into_iter();
+    let first = match items.next() {
+        Some(first) => first,
+        None => return Ok(Value::Null),
+    };
+    items.try_fold(first, |total, item| Ok((total + item)?))
+}
+
+pub(super) fn avg(arguments: Vec<Value>) -> Result<Value, ExpressionError> {
+    let items = non_null_items("avg", arguments)?;
+    let count = Value::Integer(BigInt::from(items.len()));
+    match sum(vec![Value::List(items)])? {
+        Value::Null => Ok(Value::Null),
+        total => Ok((total / count)?),
+    }
+}
+
+pub(super) fn min(arguments: Vec<Value>) -> Result<Value, ExpressionError> {
+    extreme("min", arguments, Ordering::Less)
+}
+
+pub(super) fn max(arguments: Vec<Value>) -> Result<Value, ExpressionError> {
+    extreme("max", arguments, Ordering::Greater)
+}
+
+fn extreme(
+    function: &str,
+    arguments: Vec<Value>,
+    wanted: Ordering,
+) -> Result<Value, ExpressionError> {
+    let mut result = Value::Null;
+    for item in non_null_items(function, arguments)? {
+        if result.is_null() || item.compare(&result)? == wanted {
+            result = item;
+        }
+    }
+    Ok(result)
+}
+
+pub(super) fn distinct(arguments: Vec<Value>) -> Result<Value, ExpressionError> {
+    let mut result: Vec<Value> = Vec::new();
+    for item in items("distinct", arguments)? {
+        if !result.contains(&item) {
+            result.push(item);
+        }
+    }
+    Ok(Value::List(result))
+}
+
+// Sorting is stable and puts nulls last.
+pub(super) fn sort(arguments: Vec<Value>) -> Result<Value, ExpressionError> {
+    let mut items = items("sort", arguments)?;
+    let mut error = None;
+    items.sort_by(|left, right| match (left.is_null(), right.is_null()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+        (false, false) => left.compare(right).unwrap_or_else(|e| {
+            error.get_or_insert(e);
+            Ordering::Equal
+        }),
+    });
+    match error {
+        Some(error) => Err(error.into()),
+        None => Ok(Value::List(items)),
+    }
+}
+
+pub(super) fn join(arguments: Vec<Value>) -> Result<Value, ExpressionError> {
+    let mut arguments = arguments.into_iter();
+    let (items, separator) = match (arguments.next(), arguments.next(), arguments.next()) {
+        (Some(Value::List(items)), None, None) => (items, String::new()),
+        (Some(Value::List(items)), Some(Value::String(separator)), None) => (items, separator),
+        _ => {
+            return Err(invalid_arguments(
+                "join",
+                "expected a list and an optional separator string",
+            ))
+        }
+    };
+    let parts: Vec<String> = items
+        .iter()
+        .filter(|item| !item.is_null())
+        .map(|item| item.to_string())
+        .collect();
+    Ok(Value::String(parts.join(&separator)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::function::call_function;
+    use bigdecimal::num_bigint::BigInt;
+    use expressive_data::value::Value;
+
+    fn integers(values: &[i64]) -> Value {
+        Value::List(
+            values
+                .iter()
+                .map(|value| Value::Integer(BigInt::from(*value)))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_aggregates() {
+        let list = integers(&[3, 1, 2]);
+        let cases = [
+            ("count", "3"),
+            ("sum", "6"),
+            ("min", "1"),
+            ("max", "3"),
+            ("avg", "2"),
+        ];
+        for (function, expected) in cases {
+            let result = call_function(function, vec![list.clone()]).unwrap();
+            assert_eq!(result.to_string(), expected, "function = {}", function);
+        }
+    }
+
+    #[test]
+    fn test_aggregates_skip_nulls() {
+        let list = Value::List(vec![Value::Integer(BigInt::from(2)), Value::Null]);
+        let result = call_function("sum", vec![list.clone()]).unwrap();
+        assert_eq!(result, Value::Integer(BigInt::from(2)));
+        let result = call_function("count", vec![list]).unwrap();
+        assert_eq!(result, Value::Integer(BigInt::from(2)));
+        let result = call_function("max", vec![Value::List(vec![])]).unwrap();
+        assert_eq!(result, Value::Null);
+        let result = call_function("count", vec![Value::Null]).unwrap();
+        assert_eq!(result, Value::Integer(BigInt::from(0)));
+        let result = call_function("sum", vec![Value::Null]).unwrap();
+        assert_eq!(result, Value::Null);
+        let result = call_function("avg", vec![integers(&[4])]).unwrap();
+        assert_eq!(result.to_string(), "4");
+        let result = call_function("count", vec![Value::Integer(BigInt::from(5))]);
+        assert!(result.is_err());
+        let arguments = vec![Value::Integer(BigInt::from(5)), Value::Null];
+        let result = call_function("count", arguments).unwrap();
+        assert_eq!(result, Value::Integer(BigInt::from(2)));
+    }
+
+    #[test]
+    fn test_distinct_and_sort() {
+        let result = call_function("distinct", vec![integers(&[2, 1, 2, 3, 1])]).unwrap();
+        assert_eq!(result, integers(&[2, 1, 3]));
+        let result = call_function("sort", vec![integers(&[2, 1, 3])]).unwrap();
+        assert_eq!(result, integers(&[1, 2, 3]));
+        let mixed = Value::List(vec![
+            Value::Integer(BigInt::from(1)),
+            Value::String("a".into()),
+        ]);
+        assert!(call_function("sort", vec![mixed]).is_err());
+    }
+
+    #[test]
+    fn test_join() {
+        let arguments = vec![integers(&[1, 2, 3]), Value::String(", ".into())];
+        let result = call_function("join", arguments).unwrap();
+        assert_eq!(result, Value::String("1, 2, 3".into()));
+    }
+}
@@ -0,0 +1,174 @@
+use crate::error::ExpressionError;
+use crate::function::invalid_arguments;
+use expressive_data::locale::Locale;
+use expressive_data::money::Money;
+use expressive_data::numeric::NumericValue;
+use expressive_data::value::Value;
+
+const DEFAULT_LOCALE: &str = "en-US";
+
+fn locale(argument: Option<Value>, function: &str) -> Result<Locale, ExpressionError> {
+    match argument {
+        None => Ok(Locale::from_tag(DEFAULT_LOCALE)?),
+        Some(Value::String(tag)) => Ok(Locale::from_tag(&tag)?),
+        Some(_) => Err(invalid_arguments(function, "the locale must be a string")),
+    }
+}
+
+// Date functions take either a locale, to use its usual date pattern, or a pattern.
+fn locale_and_pattern(
+    argument: Option<Value>,
+    function: &str,
+) -> Result<(Locale, Option<String>), ExpressionError> {
+    match argument {
+        None => Ok((
+            Locale::from_tag(DEFAULT_LOCALE)?,
+            Some("yyyy-MM-dd".to_owned()),
+        )),
+        Some(Value::String(text)) => match Locale::from_tag(&text) {
+            Ok(locale) => Ok((locale, None)),
+            Err(_) => Ok((Locale::from_tag(DEFAULT_LOCALE)?, Some(text))),
+        },
+        Some(_) => Err(invalid_arguments(
+            function,
+            "the pattern or locale must be a string",
+        )),
+    }
+}
+
+pub(super) fn format_number(arguments: Vec<Value>) -> Result<Value, ExpressionError> {
+    let mut arguments = arguments.into_iter();
+    let (value, locale_argument, scale) = match (
+        arguments.next(),
+        arguments.next(),
+        arguments.next(),
+        arguments.next(),
+    ) {
+        (Some(value), locale, scale, None) => (value, locale, scale),
+        _ => {
+            return Err(invalid_arguments(
+                "format_number",
+                "expected a number, an optional locale and an optional scale",
+            ))
+        }
+    };
+    let locale = locale(locale_argument, "format_number")?;
+    let scale = match scale {
+        None => None,
+        Some(Value::Integer(scale)) => Some(
+            i64::try_from(scale)
+                .map_err(|_| invalid_arguments("format_number", "the scale is too large"))?,
+        ),
+        Some(_) => {
+            return Err(invalid_arguments(
+                "format_number",
+                "the scale must be an integer",
+            ))
+        }
+    };
+    match value {
+        Value::Null => Ok(Value::Null),
+        Value::Money { amount, currency } => Ok(Value::String(
+            locale.format_money(&Money::new(amount, currency)),
+        )),
+        value => match NumericValue::try_from(value) {
+            Ok(number) => Ok(Value::String(locale.format_number(&number, scale))),
+            Err(_) => Err(invalid_arguments(
+                "format_number",
+                "only numbers and money can be formatted",
+            )),
+        },
+    }
+}
+
+// Text with a currency symbol or code parses as money, other text as a number.
+pub(super) fn parse_number(arguments: Vec<Value>) -> Result<Value, ExpressionError> {
+    let mut arguments = arguments.into_iter();
+    let (text, locale_argument) = match (arguments.next(), arguments.next(), arguments.next()) {
+        (Some(Value::Null), _, None) => return Ok(Value::Null),
+        (Some(Value::String(text)), locale, None) => (text, locale),
+        _ => {
+            return Err(invalid_arguments(
+                "parse_number",
+                "expected a string and an optional locale",
+            ))
+        }
+    };
+    let locale = locale(locale_argument, "parse_number")?;
+    match locale.parse_number(&text) {
+        Ok(number) => Ok(number.into()),
+        Err(error) => match locale.parse_money(&text) {
+            Ok(money) => Ok(money.into()),
+            Err(_) => Err(error.into()),
+        },
+    }
+}
+
+pub(super) fn format_date(arguments: Vec<Value>) -> Result<Value, ExpressionError> {
+    let mut arguments = arguments.into_iter();
+    let (date_time, pattern) = match (arguments.next(), arguments.next(), arguments.next()) {
+        (Some(Value::Null), _, None) => return Ok(Value::Null),
+        (Some(Value::DateTime(date_time)), pattern, None) => (date_time, pattern),
+        _ => {
+            return Err(invalid_arguments(
+                "format_date",
+                "expected a date and an optional pattern or locale",
+            ))
+        }
+    };
+    let (locale, pattern) = locale_and_pattern(pattern, "format_date")?;
+    Ok(Value::String(
+        locale.format_date(&date_time, pattern.as_deref()),
+    ))
+}
+
+pub(super) fn parse_date(arguments: Vec<Value>) -> Result<Value, ExpressionError> {
+    let mut arguments = arguments.into_iter();
+    let (text, pattern) = match (arguments.next(), arguments.next(), arguments.next()) {
+        (Some(Value::Null), _, None) => return Ok(Value::Null),
+        (Some(Value::String(text)), pattern, None) => (text, pattern),
+        _ => {
+            return Err(invalid_arguments(
+                "parse_date",
+                "expected a string and an optional pattern or locale",
+            ))
+        }
+    };
+    let (locale, pattern) = locale_and_pattern(pattern, "parse_date")?;
+    Ok(Value::DateTime(
+        locale.parse_date(&text, pattern.as_deref())?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::function::call_function;
+    use expressive_data::value::Value;
+
+    fn strings(values: &[&str]) -> Vec<Value> {
+        values.iter().map(|value| Value::from(*value)).collect()
+    }
+
+    #[test]
+    fn test_parse_and_format_number() {
+        let number = call_function("parse_number", strings(&["1.234,56", "nl-NL"])).unwrap();
+        assert_eq!(number.to_string(), "1234.56");
+        let arguments = vec![number, Value::from("de-DE"), Value::from(1i64)];
+        let result = call_function("format_number", arguments).unwrap();
+        assert_eq!(result, Value::from("1.234,6"));
+        let money = call_function("parse_number", strings(&["€ 12,5", "nl"])).unwrap();
+        assert_eq!(money.to_string(), "EUR 12.50");
+        let result = call_function("format_number", vec![money, Value::from("fr-FR")]).unwrap();
+        assert_eq!(result, Value::from("12,50 €"));
+        assert!(call_function("parse_number", strings(&["1.234,56"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_and_format_date() {
+        let date = call_function("parse_date", strings(&["31-12-2024", "nl-NL"])).unwrap();
+        let result = call_function("format_date", vec![date.clone()]).unwrap();
+        assert_eq!(result, Value::from("2024-12-31"));
+        let result = call_function("format_date", vec![date, Value::from("dd.MM.yy")]).unwrap();
+        assert_eq!(result, Value::from("31.12.24"));
+    }
+}
@@ -0,0 +1,259 @@
+mod higher_order;
+mod list;
+mod locale;
+
+use crate::error::ExpressionError;
+use crate::types::{common, Type};
+use expressive_data::money::Money;
+use expressive_data::numeric::NumericValue;
+use expressive_data::unit::{Quantity, UnitRegistry};
+use expressive_data::value::Value;
+
+/// A lambda passed to a higher-order function, called with the values for its parameters.
+pub(crate) type Lambda<'a> = dyn Fn(Vec<Value>) -> Result<Value, ExpressionError> + 'a;
+
+pub(crate) enum Argument<'a> {
+    Value(Value),
+    Lambda(Box<Lambda<'a>>),
+}
+
+/// Calls a built-in function, where only the higher-order functions accept lambdas as arguments.
+pub(crate) fn call(name: &str, arguments: Vec<Argument>) -> Result<Value, ExpressionError> {
+    match name {
+        "all" => higher_order::all(arguments),
+        "any" => higher_order::any(arguments),
+        "filter" => higher_order::filter(arguments),
+        "find" => higher_order::find(arguments),
+        "group_by" => higher_order::group_by(arguments),
+        "map" => higher_order::map(arguments),
+        "reduce" => higher_order::reduce(arguments),
+        "sort_by" => higher_order::sort_by(arguments),
+        _ => {
+            let arguments = arguments
+                .into_iter()
+                .map(|argument| match argument {
+                    Argument::Value(value) => Ok(value),
+                    Argument::Lambda(_) => Err(invalid_arguments(
+                        name,
+                        "a lambda can only be passed to a higher-order function",
+                    )),
+                })
+                .collect::<Result<Vec<Value>, ExpressionError>>()?;
+            call_function(name, arguments)
+        }
+    }
+}
+
+pub(crate) fn call_function(name: &str, arguments: Vec<Value>) -> Result<Value, ExpressionError> {
+    match name {
+        "allocate" => allocate(arguments),
+        "avg" => list::avg(arguments),
+        "convert" => convert(arguments),
+        "count" => list::count(arguments),
+        "distinct" => list::distinct(arguments),
+        "format_date" => locale::format_date(arguments),
+        "format_number" => locale::format_number(arguments),
+        "join" => list::join(arguments),
+        "max" => list::max(arguments),
+        "min" => list::min(arguments),
+        "parse_date" => locale::parse_date(arguments),
+        "parse_number" => locale::parse_number(arguments),
+        "sort" => list::sort(arguments),
+        "sum" => list::sum(arguments),
+        _ => Err(ExpressionError::UndefinedFunction {
+            name: name.to_owned(),
+        }),
+    }
+}
+
+pub(crate) fn is_builtin(name: &str) -> bool {
+    !matches!(
+        result_type(name, &[]),
+        Err(ExpressionError::UndefinedFunction { .. })
+    )
+}
+
+pub(crate) fn is_higher_order(name: &str) -> bool {
+    matches!(
+        name,
+        "all" | "any" | "filter" | "find" | "group_by" | "map" | "reduce" | "sort_by"
+    )
+}
+
+/// The type of the result of a function for the types of its arguments, where a lambda argument has
+/// the type of its body.
+pub(crate) fn result_type(name: &str, arguments: &[Type]) -> Result<Type, ExpressionError> {
+    let first = arguments.first().cloned().unwrap_or(Type::Any);
+    // Collection functions take either a single list or the values as separate arguments.
+    let element = match arguments {
+        [Type::List(element)] => element.as_ref().clone(),
+        arguments => common(arguments.iter().cloned()),
+    };
+    let lambda = arguments.get(1).cloned().unwrap_or(Type::Any);
+    match name {
+        "all" | "any" => Ok(Type::Boolean),
+        "filter" | "sort_by" => Ok(first),
+        "find" => Ok(match first {
+            Type::List(element) => *element,
+            _ => Type::Any,
+        }),
+        "group_by" => Ok(Type::List(Box::new(Type::Map))),
+        "map" => Ok(Type::List(Box::new(lambda))),
+        "reduce" => Ok(lambda),
+        "allocate" => Ok(Type::List(Box::new(Type::Money))),
+        "avg" | "max" | "min" | "sum" => Ok(element),
+        "convert" => Ok(Type::Quantity),
+        "count" => Ok(Type::Number),
+        "distinct" | "sort" => Ok(Type::List(Box::new(element))),
+        "format_date" | "format_number" | "join" => Ok(Type::String),
+        "parse_date" => Ok(Type::DateTime),
+        // Text that is not a number is parsed as an amount of money.
+        "parse_number" => Ok(Type::Any),
+        _ => Err(ExpressionError::UndefinedFunction {
+            name: name.to_owned(),
+        }),
+    }
+}
+
+fn invalid_arguments(function: &str, message: &str) -> ExpressionError {
+    ExpressionError::InvalidArguments {
+        function: function.to_owned(),
+        message: message.to_owned(),
+    }
+}
+
+fn allocate(arguments: Vec<Value>) -> Result<Value, ExpressionError> {
+    let mut arguments = arguments.into_iter();
+    let money = match arguments.next() {
+        Some(Value::Money { amount, currency }) => Money::new(amount, currency),
+        _ => {
+            return Err(invalid_arguments(
+                "allocate",
+                "the first argument must be an amount of money",
+            ))
+        }
+    };
+    let ratios = arguments
+        .map(|argument| match argument {
+            Value::Integer(i) => Ok(NumericValue::Integer(i)),
+            Value::Decimal(d) => Ok(NumericValue::Decimal(d)),
+            _ => Err(invalid_arguments("allocate", "the ratios must be numbers")),
+        })
+        .collect::<Result<Vec<NumericValue>, ExpressionError>>()?;
+    let parts = money.allocate(&ratios)?;
+    Ok(Value::List(parts.into_iter().map(Value::from).collect()))
+}
+
+fn convert(arguments: Vec<Value>) -> Result<Value, ExpressionError> {
+    let mut arguments = arguments.into_iter();
+    let (quantity, unit, scale) = match (arguments.next(), arguments.next(), arguments.next()) {
+        (Some(Value::Quantity { magnitude, unit }), Some(Value::String(target)), scale) => {
+            (Quantity::new(magnitude, unit), target, scale)
+        }
+        _ => {
+            return Err(invalid_arguments(
+                "convert",
+                "expected a quantity, a unit and an optional rounding scale",
+            ))
+        }
+    };
+    let unit = UnitRegistry::standard().parse(&unit)?;
+    let converted = match scale {
+        None => quantity.convert(&unit)?,
+        Some(Value::Integer(scale)) => {
+            let scale = i64::try_from(scale)
+                .map_err(|_| invalid_arguments("convert", "the rounding scale is too large"))?;
+            quantity.convert_rounded(&unit, scale)?
+        }
+        Some(_) => {
+            return Err(invalid_arguments(
+                "convert",
+                "the rounding scale must be an integer",
+            ))
+        }
+    };
+    Ok(converted.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bigdecimal::num_bigint::BigInt;
+    use bigdecimal::BigDecimal;
+    use expressive_data::money::Currency;
+    use std::str::FromStr;
+
+    fn euros(amount: &str) -> Value {
+        Money::new(
+            BigDecimal::from_str(amount).unwrap(),
+            Currency::from_code("EUR").unwrap(),
+        )
+        .into()
+    }
+
+    #[test]
+    fn test_allocate() {
+        let ratios = vec![
+            euros("100"),
+            Value::Integer(BigInt::from(1)),
+            Value::Integer(BigInt::from(1)),
+            Value::Integer(BigInt::from(1)),
+        ];
+        let result = call_function("allocate", ratios).unwrap();
+        assert_eq!(
+            result,
+            Value::List(vec![euros("33.34"), euros("33.33"), euros("33.33")])
+        );
+    }
+
+    #[test]
+    fn test_allocate_requires_money() {
+        let arguments = vec![
+            Value::Integer(BigInt::from(100)),
+            Value::Integer(BigInt::from(1)),
+        ];
+        let result = call_function("allocate", arguments);
+        assert!(matches!(
+            result,
+            Err(ExpressionError::InvalidArguments { .. })
+        ));
+    }
+
+    #[test]
+    fn test_lambda_requires_higher_order_function() {
+        let lambda: Box<Lambda> = Box::new(|_| Ok(Value::Null));
+        let result = call("sum", vec![Argument::Lambda(lambda)]);
+        assert!(matches!(
+            result,
+            Err(ExpressionError::InvalidArguments { .. })
+        ));
+    }
+
+    #[test]
+    fn test_undefined_function() {
+        let result = call_function("nope", vec![]);
+        assert!(matches!(
+            result,
+            Err(ExpressionError::UndefinedFunction { .. })
+        ));
+    }
+
+    #[test]
+    fn test_convert() {
+        let weight = Value::from(Quantity::new(
+            NumericValue::Integer(BigInt::from(1)),
+            UnitRegistry::standard().parse("kg").unwrap(),
+        ));
+        let result = call_function("convert", vec![weight.clone(), Value::String("g".into())]);
+        assert_eq!(result.unwrap().to_string(), "1000 g");
+        let result = call_function("convert", vec![weight.clone(), Value::String("lb".into())]);
+        assert!(result.is_err());
+        let arguments = vec![
+            weight,
+            Value::String("lb".into()),
+            Value::Integer(BigInt::from(3)),
+        ];
+        let result = call_function("convert", arguments);
+        assert_eq!(result.unwrap().to_string(), "2.205 lb");
+    }
+}
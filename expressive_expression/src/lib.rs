@@ -1,4 +1,8 @@
+pub mod ast;
+pub mod diff;
+pub mod error;
 pub mod evaluate;
 
+mod function;
 mod parser;
-mod error;
+pub mod types;
@@ -0,0 +1,306 @@
+use crate::ast::{
+    Accessor, Associativity, BinaryOperator, Expression, FunctionDefinition, PostfixOperator,
+    PrefixOperator,
+};
+use crate::error::ExpressionError;
+use crate::parser::Rule;
+use bigdecimal::num_bigint::BigInt;
+use bigdecimal::BigDecimal;
+use expressive_data::money::{Currency, Money};
+use expressive_data::numeric::{NumericValue, MAX_EXPONENT};
+use expressive_data::unit::{Quantity, UnitRegistry};
+use expressive_data::value::Value;
+use pest::iterators::{Pair, Pairs};
+use pest::pratt_parser::{Assoc, Op, PrattParser};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+pub(crate) fn build(pair: Pair<Rule>) -> Result<Expression, ExpressionError> {
+    match pair.as_rule() {
+        Rule::integer | Rule::decimal | Rule::hexadecimal | Rule::binary | Rule::percentage => {
+            Ok(literal(number(&pair)?.into()))
+        }
+        Rule::money => {
+            let mut inner = pair.into_inner();
+            let (currency_pair, amount_pair) = inner
+                .next()
+                .zip(inner.next())
+                .ok_or(ExpressionError::UnexpectedAbstractSyntaxTree)?;
+            let currency = Currency::from_code(currency_pair.as_str())?;
+            let amount = match number(&amount_pair)? {
+                NumericValue::Decimal(d) => d,
+                NumericValue::Integer(i) => BigDecimal::from(i),
+            };
+            Ok(literal(Money::new(amount, currency).into()))
+        }
+        Rule::quantity => {
+            let mut inner = pair.into_inner();
+            let (magnitude_pair, unit_pair) = inner
+                .next()
+                .zip(inner.next())
+                .ok_or(ExpressionError::UnexpectedAbstractSyntaxTree)?;
+            let magnitude = number(&magnitude_pair)?;
+            let unit = UnitRegistry::standard().parse(unit_pair.as_str())?;
+            Ok(literal(Quantity::new(magnitude, unit).into()))
+        }
+        Rule::string => {
+            let content = pair
+                .into_inner()
+                .next()
+                .ok_or(ExpressionError::UnexpectedAbstractSyntaxTree)?;
+            Ok(literal(Value::String(content.as_str().to_owned())))
+        }
+        Rule::boolean => Ok(literal(Value::Boolean(pair.as_str() == "true"))),
+        Rule::null => Ok(literal(Value::Null)),
+        Rule::variable_name => Ok(Expression::Variable {
+            name: pair.as_str().to_owned(),
+        }),
+        Rule::function_call => {
+            let mut inner = pair.into_inner();
+            let name_pair = inner
+                .next()
+                .ok_or(ExpressionError::UnexpectedAbstractSyntaxTree)?;
+            Ok(Expression::FunctionCall {
+                name: name_pair.as_str().to_owned(),
+                arguments: inner.map(build).collect::<Result<_, _>>()?,
+            })
+        }
+        Rule::lambda => {
+            let mut inner = pair.into_inner();
+            let (parameters_pair, body_pair) = inner
+                .next()
+                .zip(inner.next())
+                .ok_or(ExpressionError::UnexpectedAbstractSyntaxTree)?;
+            Ok(Expression::Lambda {
+                parameters: parameters_pair
+                    .into_inner()
+                    .map(|parameter| parameter.as_str().to_owned())
+                    .collect(),
+                body: Box::new(build(body_pair)?),
+            })
+        }
+        Rule::list => Ok(Expression::List {
+            items: pair.into_inner().map(build).collect::<Result<_, _>>()?,
+        }),
+        Rule::access => {
+            let mut inner = pair.into_inner();
+            let primary_pair = inner
+                .next()
+                .ok_or(ExpressionError::UnexpectedAbstractSyntaxTree)?;
+            inner.try_fold(build(primary_pair)?, |target, accessor_pair| {
+                let rule = accessor_pair.as_rule();
+                let key_pair = accessor_pair
+                    .into_inner()
+                    .next()
+                    .ok_or(ExpressionError::UnexpectedAbstractSyntaxTree)?;
+                let accessor = match rule {
+                    Rule::field => Accessor::Field(key_pair.as_str().to_owned()),
+                    _ => Accessor::Index(Box::new(build(key_pair)?)),
+                };
+                Ok(Expression::Access {
+                    target: Box::new(target),
+                    accessor,
+                })
+            })
+        }
+        Rule::parenthesized => {
+            let expression_pair = pair
+                .into_inner()
+                .next()
+                .ok_or(ExpressionError::UnexpectedAbstractSyntaxTree)?;
+            build(expression_pair)
+        }
+        Rule::expression => resolve_terminals(pair.into_inner()),
+        _ => Err(ExpressionError::UnexpectedAbstractSyntaxTree),
+    }
+}
+
+pub(crate) fn build_function(pair: Pair<Rule>) -> Result<FunctionDefinition, ExpressionError> {
+    let mut inner = pair
+        .into_inner()
+        .skip_while(|pair| pair.as_rule() == Rule::function_keyword);
+    match (inner.next(), inner.next(), inner.next()) {
+        (Some(name_pair), Some(parameters_pair), Some(body_pair)) => Ok(FunctionDefinition {
+            name: name_pair.as_str().to_owned(),
+            parameters: parameters_pair
+                .into_inner()
+                .map(|parameter| parameter.as_str().to_owned())
+                .collect(),
+            body: build(body_pair)?,
+        }),
+        _ => Err(ExpressionError::UnexpectedAbstractSyntaxTree),
+    }
+}
+
+// Numeric literals are parsed exactly from their digits, never through floating point.
+fn number(pair: &Pair<Rule>) -> Result<NumericValue, ExpressionError> {
+    let invalid = || ExpressionError::EvaluationError {
+        expression: pair.as_str().to_owned(),
+    };
+    let text = pair.as_str().replace('_', "");
+    let (negative, unsigned) = match text.strip_prefix('-') {
+        Some(unsigned) => (true, unsigned),
+        None => (false, text.as_str()),
+    };
+    let number = match pair.as_rule() {
+        Rule::integer => NumericValue::Integer(BigInt::from_str(unsigned).map_err(|_| invalid())?),
+        Rule::hexadecimal | Rule::binary => {
+            let (digits, radix) = match unsigned.split_at(2) {
+                ("0x", digits) => (digits, 16),
+                (_, digits) => (digits, 2),
+            };
+            let integer = BigInt::parse_bytes(digits.as_bytes(), radix).ok_or_else(invalid)?;
+            NumericValue::Integer(integer)
+        }
+        Rule::percentage => {
+            let decimal = decimal(unsigned.trim_end_matches('%')).ok_or_else(invalid)?;
+            let (digits, scale) = decimal.into_bigint_and_exponent();
+            NumericValue::Decimal(BigDecimal::new(digits, scale + 2))
+        }
+        _ => {
+            check_exponent(unsigned)?;
+            NumericValue::Decimal(decimal(unsigned).ok_or_else(invalid)?)
+        }
+    };
+    Ok(match (negative, number) {
+        (true, NumericValue::Integer(i)) => NumericValue::Integer(-i),
+        (true, NumericValue::Decimal(d)) => NumericValue::Decimal(-d),
+        (false, number) => number,
+    })
+}
+
+// An exponent is bounded like the exponent of a power, since `1e999999999` would otherwise expand
+// to an integer with a billion digits.
+fn check_exponent(text: &str) -> Result<(), ExpressionError> {
+    let Some((_, exponent)) = text.split_once(['e', 'E']) else {
+        return Ok(());
+    };
+    let digits = exponent
+        .trim_start_matches(['+', '-'])
+        .trim_start_matches('0');
+    if digits.len() > MAX_EXPONENT.to_string().len()
+        || digits
+            .parse::<u32>()
+            .is_ok_and(|exponent| exponent > MAX_EXPONENT)
+    {
+        return Err(ExpressionError::ParseError {
+            message: format!(
+                "the exponent of '{}' is larger than the maximum of {}",
+                text, MAX_EXPONENT
+            ),
+        });
+    }
+    Ok(())
+}
+
+// Decimals written with a positive exponent, e.g. `1e3`, are kept without one.
+fn decimal(text: &str) -> Option<BigDecimal> {
+    let decimal = match text.starts_with('.') {
+        true => BigDecimal::from_str(&format!("0{}", text)),
+        false => BigDecimal::from_str(text),
+    }
+    .ok()?;
+    match decimal.as_bigint_and_exponent().1 < 0 {
+        true => Some(decimal.with_scale(0)),
+        false => Some(decimal),
+    }
+}
+
+fn literal(value: Value) -> Expression {
+    Expression::Literal { value }
+}
+
+const BINARY_OPERATORS: [(Rule, BinaryOperator); 15] = [
+    (Rule::addition, BinaryOperator::Add),
+    (Rule::subtraction, BinaryOperator::Subtract),
+    (Rule::multiplication, BinaryOperator::Multiply),
+    (Rule::division, BinaryOperator::Divide),
+    (Rule::power, BinaryOperator::Power),
+    (Rule::equal, BinaryOperator::Equal),
+    (Rule::not_equal, BinaryOperator::NotEqual),
+    (Rule::less, BinaryOperator::Less),
+    (Rule::less_or_equal, BinaryOperator::LessOrEqual),
+    (Rule::greater, BinaryOperator::Greater),
+    (Rule::greater_or_equal, BinaryOperator::GreaterOrEqual),
+    (Rule::membership, BinaryOperator::In),
+    (Rule::logical_and, BinaryOperator::And),
+    (Rule::logical_or, BinaryOperator::Or),
+    (Rule::null_coalescing, BinaryOperator::Coalesce),
+];
+
+const PREFIX_OPERATORS: [(Rule, PrefixOperator); 1] = [(Rule::logical_not, PrefixOperator::Not)];
+
+const POSTFIX_OPERATORS: [(Rule, PostfixOperator); 2] = [
+    (Rule::is_null, PostfixOperator::IsNull),
+    (Rule::is_not_null, PostfixOperator::IsNotNull),
+];
+
+// The Pratt parser is built from the precedence and associativity the operators declare, from the
+// loosest to the tightest binding level.
+fn pratt_parser() -> &'static PrattParser<Rule> {
+    static PARSER: OnceLock<PrattParser<Rule>> = OnceLock::new();
+    PARSER.get_or_init(|| {
+        let mut levels: BTreeMap<u32, Vec<Op<Rule>>> = BTreeMap::new();
+        for (rule, operator) in BINARY_OPERATORS {
+            let associativity = match operator.associativity() {
+                Associativity::Left => Assoc::Left,
+                Associativity::Right => Assoc::Right,
+            };
+            levels
+                .entry(operator.precedence())
+                .or_default()
+                .push(Op::infix(rule, associativity));
+        }
+        for (rule, operator) in PREFIX_OPERATORS {
+            levels
+                .entry(operator.precedence())
+                .or_default()
+                .push(Op::prefix(rule));
+        }
+        for (rule, operator) in POSTFIX_OPERATORS {
+            levels
+                .entry(operator.precedence())
+                .or_default()
+                .push(Op::postfix(rule));
+        }
+        levels
+            .into_values()
+            .filter_map(|level| level.into_iter().reduce(|left, right| left | right))
+            .fold(PrattParser::new(), |parser, level| parser.op(level))
+    })
+}
+
+// Builds the tree for a sequence of terms interleaved with operators.
+fn resolve_terminals(pairs: Pairs<Rule>) -> Result<Expression, ExpressionError> {
+    pratt_parser()
+        .map_primary(build)
+        .map_infix(|left, operator, right| {
+            Ok(Expression::Binary {
+                operator: lookup(&BINARY_OPERATORS, &operator)?,
+                left: Box::new(left?),
+                right: Box::new(right?),
+            })
+        })
+        .map_prefix(|operator, operand| {
+            Ok(Expression::Prefix {
+                operator: lookup(&PREFIX_OPERATORS, &operator)?,
+                operand: Box::new(operand?),
+            })
+        })
+        .map_postfix(|operand, operator| {
+            Ok(Expression::Postfix {
+                operator: lookup(&POSTFIX_OPERATORS, &operator)?,
+                operand: Box::new(operand?),
+            })
+        })
+        .parse(pairs)
+}
+
+fn lookup<T: Copy>(operators: &[(Rule, T)], pair: &Pair<Rule>) -> Result<T, ExpressionError> {
+    operators
+        .iter()
+        .find(|(rule, _)| *rule == pair.as_rule())
+        .map(|(_, operator)| *operator)
+        .ok_or(ExpressionError::UnexpectedAbstractSyntaxTree)
+}
@@ -1,9 +1,38 @@
+mod build;
+
+use crate::ast::{Expression, FunctionDefinition};
+use crate::error::ExpressionError;
+use pest::Parser;
 use pest_derive::Parser;
 
 #[derive(Parser)]
 #[grammar = "./parser/grammar.pest"]
 pub struct ExpressionParser;
 
+pub(crate) fn parse(input: &str) -> Result<Expression, ExpressionError> {
+    let mut pairs = ExpressionParser::parse(Rule::input, input).map_err(|error| {
+        ExpressionError::ParseError {
+            message: format!("{}", error),
+        }
+    })?;
+    let expression_pair = pairs
+        .next()
+        .ok_or(ExpressionError::UnexpectedAbstractSyntaxTree)?;
+    build::build(expression_pair)
+}
+
+pub(crate) fn parse_function(input: &str) -> Result<FunctionDefinition, ExpressionError> {
+    let mut pairs = ExpressionParser::parse(Rule::definition_input, input).map_err(|error| {
+        ExpressionError::ParseError {
+            message: format!("{}", error),
+        }
+    })?;
+    let definition_pair = pairs
+        .next()
+        .ok_or(ExpressionError::UnexpectedAbstractSyntaxTree)?;
+    build::build_function(definition_pair)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::parser::{ExpressionParser, Rule};
@@ -70,6 +99,10 @@ mod tests {
                 rule: Rule::expression,
                 value: input,
             },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "1 ",
+            },
             ExpectedPair {
                 rule: Rule::integer,
                 value: "1",
@@ -78,6 +111,10 @@ mod tests {
                 rule: Rule::addition,
                 value: "+",
             },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "1 ",
+            },
             ExpectedPair {
                 rule: Rule::integer,
                 value: "1",
@@ -86,6 +123,10 @@ mod tests {
                 rule: Rule::addition,
                 value: "+",
             },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "(2 - 2)",
+            },
             ExpectedPair {
                 rule: Rule::parenthesized,
                 value: "(2 - 2)",
@@ -94,6 +135,10 @@ mod tests {
                 rule: Rule::expression,
                 value: "2 - 2",
             },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "2 ",
+            },
             ExpectedPair {
                 rule: Rule::integer,
                 value: "2",
@@ -102,6 +147,10 @@ mod tests {
                 rule: Rule::subtraction,
                 value: "-",
             },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "2",
+            },
             ExpectedPair {
                 rule: Rule::integer,
                 value: "2",
@@ -116,7 +165,7 @@ mod tests {
 
     #[test]
     fn test_invalid_expression() {
-        let input = "garbage";
+        let input = "garbage in garbage out";
         assert_expression_is_invalid(input);
     }
 
@@ -128,6 +177,10 @@ mod tests {
                 rule: Rule::expression,
                 value: "a + 2",
             },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "a ",
+            },
             ExpectedPair {
                 rule: Rule::variable_name,
                 value: "a",
@@ -136,6 +189,10 @@ mod tests {
                 rule: Rule::addition,
                 value: "+",
             },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "2",
+            },
             ExpectedPair {
                 rule: Rule::integer,
                 value: "2",
@@ -148,6 +205,60 @@ mod tests {
         assert_expression_is_valid(input, &expected_pairs);
     }
 
+    #[test]
+    fn test_single_terminal_expression() {
+        let expected_pairs = [
+            ExpectedPair {
+                rule: Rule::expression,
+                value: "42",
+            },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "42",
+            },
+            ExpectedPair {
+                rule: Rule::integer,
+                value: "42",
+            },
+            ExpectedPair {
+                rule: Rule::EOI,
+                value: "",
+            },
+        ];
+        assert_expression_is_valid("42", &expected_pairs);
+        let expected_pairs = [
+            ExpectedPair {
+                rule: Rule::expression,
+                value: "(price)",
+            },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "(price)",
+            },
+            ExpectedPair {
+                rule: Rule::parenthesized,
+                value: "(price)",
+            },
+            ExpectedPair {
+                rule: Rule::expression,
+                value: "price",
+            },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "price",
+            },
+            ExpectedPair {
+                rule: Rule::variable_name,
+                value: "price",
+            },
+            ExpectedPair {
+                rule: Rule::EOI,
+                value: "",
+            },
+        ];
+        assert_expression_is_valid("(price)", &expected_pairs);
+    }
+
     #[test]
     fn test_simple_decimal_expression() {
         let input = "3.14 * 2";
@@ -156,6 +267,10 @@ mod tests {
                 rule: Rule::expression,
                 value: "3.14 * 2",
             },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "3.14 ",
+            },
             ExpectedPair {
                 rule: Rule::decimal,
                 value: "3.14",
@@ -164,6 +279,10 @@ mod tests {
                 rule: Rule::multiplication,
                 value: "*",
             },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "2",
+            },
             ExpectedPair {
                 rule: Rule::integer,
                 value: "2",
@@ -184,6 +303,10 @@ mod tests {
                 rule: Rule::expression,
                 value: "a * (b + c) - d / e",
             },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "a ",
+            },
             ExpectedPair {
                 rule: Rule::variable_name,
                 value: "a",
@@ -192,6 +315,10 @@ mod tests {
                 rule: Rule::multiplication,
                 value: "*",
             },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "(b + c) ",
+            },
             ExpectedPair {
                 rule: Rule::parenthesized,
                 value: "(b + c)",
@@ -200,6 +327,10 @@ mod tests {
                 rule: Rule::expression,
                 value: "b + c",
             },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "b ",
+            },
             ExpectedPair {
                 rule: Rule::variable_name,
                 value: "b",
@@ -208,6 +339,10 @@ mod tests {
                 rule: Rule::addition,
                 value: "+",
             },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "c",
+            },
             ExpectedPair {
                 rule: Rule::variable_name,
                 value: "c",
@@ -216,6 +351,10 @@ mod tests {
                 rule: Rule::subtraction,
                 value: "-",
             },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "d ",
+            },
             ExpectedPair {
                 rule: Rule::variable_name,
                 value: "d",
@@ -224,6 +363,10 @@ mod tests {
                 rule: Rule::division,
                 value: "/",
             },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "e",
+            },
             ExpectedPair {
                 rule: Rule::variable_name,
                 value: "e",
@@ -244,6 +387,10 @@ mod tests {
                 rule: Rule::expression,
                 value: "1+  1 ",
             },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "1",
+            },
             ExpectedPair {
                 rule: Rule::integer,
                 value: "1",
@@ -252,6 +399,10 @@ mod tests {
                 rule: Rule::addition,
                 value: "+",
             },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "1 ",
+            },
             ExpectedPair {
                 rule: Rule::integer,
                 value: "1",
@@ -272,6 +423,10 @@ mod tests {
                 rule: Rule::expression,
                 value: "((1+2)*3) - 4.5/var",
             },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "((1+2)*3) ",
+            },
             ExpectedPair {
                 rule: Rule::parenthesized,
                 value: "((1+2)*3)",
@@ -280,6 +435,10 @@ mod tests {
                 rule: Rule::expression,
                 value: "(1+2)*3",
             },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "(1+2)",
+            },
             ExpectedPair {
                 rule: Rule::parenthesized,
                 value: "(1+2)",
@@ -288,6 +447,10 @@ mod tests {
                 rule: Rule::expression,
                 value: "1+2",
             },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "1",
+            },
             ExpectedPair {
                 rule: Rule::integer,
                 value: "1",
@@ -296,6 +459,10 @@ mod tests {
                 rule: Rule::addition,
                 value: "+",
             },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "2",
+            },
             ExpectedPair {
                 rule: Rule::integer,
                 value: "2",
@@ -304,6 +471,10 @@ mod tests {
                 rule: Rule::multiplication,
                 value: "*",
             },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "3",
+            },
             ExpectedPair {
                 rule: Rule::integer,
                 value: "3",
@@ -312,6 +483,10 @@ mod tests {
                 rule: Rule::subtraction,
                 value: "-",
             },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "4.5",
+            },
             ExpectedPair {
                 rule: Rule::decimal,
                 value: "4.5",
@@ -320,6 +495,10 @@ mod tests {
                 rule: Rule::division,
                 value: "/",
             },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "var",
+            },
             ExpectedPair {
                 rule: Rule::variable_name,
                 value: "var",
@@ -332,6 +511,308 @@ mod tests {
         assert_expression_is_valid(input, &expected_pairs);
     }
 
+    #[test]
+    fn test_money_and_function_call_expression() {
+        let input = "EUR 12.50 + sum(a, 1)";
+        let expected_pairs = vec![
+            ExpectedPair {
+                rule: Rule::expression,
+                value: input,
+            },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "EUR 12.50 ",
+            },
+            ExpectedPair {
+                rule: Rule::money,
+                value: "EUR 12.50",
+            },
+            ExpectedPair {
+                rule: Rule::currency_code,
+                value: "EUR",
+            },
+            ExpectedPair {
+                rule: Rule::decimal,
+                value: "12.50",
+            },
+            ExpectedPair {
+                rule: Rule::addition,
+                value: "+",
+            },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "sum(a, 1)",
+            },
+            ExpectedPair {
+                rule: Rule::function_call,
+                value: "sum(a, 1)",
+            },
+            ExpectedPair {
+                rule: Rule::function_name,
+                value: "sum",
+            },
+            ExpectedPair {
+                rule: Rule::expression,
+                value: "a",
+            },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "a",
+            },
+            ExpectedPair {
+                rule: Rule::variable_name,
+                value: "a",
+            },
+            ExpectedPair {
+                rule: Rule::expression,
+                value: "1",
+            },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "1",
+            },
+            ExpectedPair {
+                rule: Rule::integer,
+                value: "1",
+            },
+            ExpectedPair {
+                rule: Rule::EOI,
+                value: "",
+            },
+        ];
+        assert_expression_is_valid(input, &expected_pairs);
+    }
+
+    #[test]
+    fn test_numeric_literal_expression() {
+        let input = "0x1f + 1_000.5e-3 * 15%";
+        let expected_pairs = vec![
+            ExpectedPair {
+                rule: Rule::expression,
+                value: input,
+            },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "0x1f ",
+            },
+            ExpectedPair {
+                rule: Rule::hexadecimal,
+                value: "0x1f",
+            },
+            ExpectedPair {
+                rule: Rule::addition,
+                value: "+",
+            },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "1_000.5e-3 ",
+            },
+            ExpectedPair {
+                rule: Rule::decimal,
+                value: "1_000.5e-3",
+            },
+            ExpectedPair {
+                rule: Rule::multiplication,
+                value: "*",
+            },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "15%",
+            },
+            ExpectedPair {
+                rule: Rule::percentage,
+                value: "15%",
+            },
+            ExpectedPair {
+                rule: Rule::EOI,
+                value: "",
+            },
+        ];
+        assert_expression_is_valid(input, &expected_pairs);
+    }
+
+    #[test]
+    fn test_quantity_expression() {
+        let input = "3 m/s * t";
+        let expected_pairs = vec![
+            ExpectedPair {
+                rule: Rule::expression,
+                value: input,
+            },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "3 m/s ",
+            },
+            ExpectedPair {
+                rule: Rule::quantity,
+                value: "3 m/s",
+            },
+            ExpectedPair {
+                rule: Rule::integer,
+                value: "3",
+            },
+            ExpectedPair {
+                rule: Rule::unit,
+                value: "m/s",
+            },
+            ExpectedPair {
+                rule: Rule::multiplication,
+                value: "*",
+            },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "t",
+            },
+            ExpectedPair {
+                rule: Rule::variable_name,
+                value: "t",
+            },
+            ExpectedPair {
+                rule: Rule::EOI,
+                value: "",
+            },
+        ];
+        assert_expression_is_valid(input, &expected_pairs);
+    }
+
+    #[test]
+    fn test_null_handling_expression() {
+        let input = "a ?? null is not null and nullable";
+        let expected_pairs = vec![
+            ExpectedPair {
+                rule: Rule::expression,
+                value: input,
+            },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "a ",
+            },
+            ExpectedPair {
+                rule: Rule::variable_name,
+                value: "a",
+            },
+            ExpectedPair {
+                rule: Rule::null_coalescing,
+                value: "??",
+            },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "null ",
+            },
+            ExpectedPair {
+                rule: Rule::null,
+                value: "null",
+            },
+            ExpectedPair {
+                rule: Rule::is_not_null,
+                value: "is not null",
+            },
+            ExpectedPair {
+                rule: Rule::logical_and,
+                value: "and",
+            },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "nullable",
+            },
+            ExpectedPair {
+                rule: Rule::variable_name,
+                value: "nullable",
+            },
+            ExpectedPair {
+                rule: Rule::EOI,
+                value: "",
+            },
+        ];
+        assert_expression_is_valid(input, &expected_pairs);
+    }
+
+    #[test]
+    fn test_invalid_keyword_as_variable() {
+        let input = "and + 1";
+        assert_expression_is_invalid(input);
+    }
+
+    #[test]
+    fn test_field_and_index_access() {
+        let input = "row.lines[0][\"unit price\"] * 2";
+        let expected_pairs = vec![
+            ExpectedPair {
+                rule: Rule::expression,
+                value: input,
+            },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "row.lines[0][\"unit price\"]",
+            },
+            ExpectedPair {
+                rule: Rule::variable_name,
+                value: "row",
+            },
+            ExpectedPair {
+                rule: Rule::field,
+                value: ".lines",
+            },
+            ExpectedPair {
+                rule: Rule::field_name,
+                value: "lines",
+            },
+            ExpectedPair {
+                rule: Rule::index,
+                value: "[0]",
+            },
+            ExpectedPair {
+                rule: Rule::expression,
+                value: "0",
+            },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "0",
+            },
+            ExpectedPair {
+                rule: Rule::integer,
+                value: "0",
+            },
+            ExpectedPair {
+                rule: Rule::index,
+                value: "[\"unit price\"]",
+            },
+            ExpectedPair {
+                rule: Rule::expression,
+                value: "\"unit price\"",
+            },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "\"unit price\"",
+            },
+            ExpectedPair {
+                rule: Rule::string,
+                value: "\"unit price\"",
+            },
+            ExpectedPair {
+                rule: Rule::string_content,
+                value: "unit price",
+            },
+            ExpectedPair {
+                rule: Rule::multiplication,
+                value: "*",
+            },
+            ExpectedPair {
+                rule: Rule::access,
+                value: "2",
+            },
+            ExpectedPair {
+                rule: Rule::integer,
+                value: "2",
+            },
+            ExpectedPair {
+                rule: Rule::EOI,
+                value: "",
+            },
+        ];
+        assert_expression_is_valid(input, &expected_pairs);
+    }
+
     #[test]
     fn test_invalid_newline() {
         let input = "1 +\n2";
@@ -350,6 +831,17 @@ mod tests {
         assert_expression_is_invalid(input);
     }
 
+    #[test]
+    fn test_deeply_nested_expression() {
+        // Each level would be parsed several times over if the rules backtracked over expressions.
+        let depth = 15;
+        let input = format!("{}y{}", "(abs([".repeat(depth), "])[0] + 1)".repeat(depth));
+        let start = std::time::Instant::now();
+        let result = crate::parser::parse(&input);
+        assert!(result.is_ok(), "expected '{}' to parse", input);
+        assert!(start.elapsed() < std::time::Duration::from_secs(1));
+    }
+
     #[test]
     fn test_invalid_decimal_format() {
         let input = "1.";
@@ -0,0 +1,392 @@
+use crate::ast::{Accessor, BinaryOperator, Expression, PostfixOperator, PrefixOperator};
+use crate::error::ExpressionError;
+use crate::function;
+use expressive_data::value::Value;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// The static type of an expression or variable.
+///
+/// Integers and decimals are both numbers. Null is not a type of its own: any value can be null, so
+/// the `null` literal has type `Any`, which is accepted everywhere a type is expected.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Type {
+    Any,
+    Boolean,
+    Number,
+    Money,
+    Quantity,
+    String,
+    DateTime,
+    List(Box<Type>),
+    Map,
+}
+
+impl Type {
+    pub fn of(value: &Value) -> Type {
+        match value {
+            Value::Null => Type::Any,
+            Value::Boolean(_) => Type::Boolean,
+            Value::Integer(_) | Value::Decimal(_) => Type::Number,
+            Value::Money { .. } => Type::Money,
+            Value::Quantity { .. } => Type::Quantity,
+            Value::String(_) => Type::String,
+            Value::DateTime(_) => Type::DateTime,
+            Value::List(items) => Type::List(Box::new(common(items.iter().map(Type::of)))),
+            Value::Map(_) => Type::Map,
+        }
+    }
+
+    /// Whether a value of the other type can be used where this type is expected.
+    pub fn accepts(&self, other: &Type) -> bool {
+        match (self, other) {
+            (Type::Any, _) | (_, Type::Any) => true,
+            (Type::List(expected), Type::List(actual)) => expected.accepts(actual),
+            (expected, actual) => expected == actual,
+        }
+    }
+
+    // The type of the elements of a list, where a value of unknown type may turn out to be a list.
+    fn element(&self) -> Option<Type> {
+        match self {
+            Type::List(element) => Some(element.as_ref().clone()),
+            Type::Any => Some(Type::Any),
+            _ => None,
+        }
+    }
+}
+
+// The most specific type that all types share, which is `Any` when they differ.
+pub(crate) fn common(mut types: impl Iterator<Item = Type>) -> Type {
+    let Some(first) = types.next() else {
+        return Type::Any;
+    };
+    types.fold(first, |common, next| match (common, next) {
+        (Type::Any, next) => next,
+        (common, Type::Any) => common,
+        (Type::List(left), Type::List(right)) => Type::List(Box::new(common_pair(*left, *right))),
+        (common, next) if common == next => common,
+        _ => Type::Any,
+    })
+}
+
+fn common_pair(left: Type, right: Type) -> Type {
+    common([left, right].into_iter())
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Any => write!(f, "any"),
+            Type::Boolean => write!(f, "boolean"),
+            Type::Number => write!(f, "number"),
+            Type::Money => write!(f, "money"),
+            Type::Quantity => write!(f, "quantity"),
+            Type::String => write!(f, "string"),
+            Type::DateTime => write!(f, "date time"),
+            Type::List(element) if **element == Type::Any => write!(f, "list"),
+            Type::List(element) => write!(f, "list of {}", element),
+            Type::Map => write!(f, "map"),
+        }
+    }
+}
+
+/// Parses the names that types display as, e.g. `number` or `list of money`.
+impl FromStr for Type {
+    type Err = ExpressionError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.trim() {
+            "any" => Ok(Type::Any),
+            "boolean" => Ok(Type::Boolean),
+            "number" => Ok(Type::Number),
+            "money" => Ok(Type::Money),
+            "quantity" => Ok(Type::Quantity),
+            "string" => Ok(Type::String),
+            "date time" => Ok(Type::DateTime),
+            "list" => Ok(Type::List(Box::new(Type::Any))),
+            "map" => Ok(Type::Map),
+            other => match other.strip_prefix("list of ") {
+                Some(element) => Ok(Type::List(Box::new(element.parse()?))),
+                None => Err(ExpressionError::UnknownType {
+                    name: other.to_owned(),
+                }),
+            },
+        }
+    }
+}
+
+/// Checks that the operators, functions and field accesses in an expression are applied to values
+/// of the right type, given the types of its variables, and returns the type of its result.
+///
+/// The check is conservative: whatever cannot be known before evaluation, such as the fields of a
+/// map, has type `Any` and is accepted.
+pub fn check(
+    expression: &Expression,
+    variables: &HashMap<String, Type>,
+) -> Result<Type, ExpressionError> {
+    match expression {
+        Expression::Literal { value } => Ok(Type::of(value)),
+        Expression::Variable { name } => {
+            variables
+                .get(name)
+                .cloned()
+                .ok_or_else(|| ExpressionError::UndefinedVariable {
+                    name: name.to_owned(),
+                })
+        }
+        Expression::List { items } => {
+            let items = items
+                .iter()
+                .map(|item| check(item, variables))
+                .collect::<Result<Vec<Type>, ExpressionError>>()?;
+            Ok(Type::List(Box::new(common(items.into_iter()))))
+        }
+        Expression::FunctionCall { name, arguments } => check_call(name, arguments, variables),
+        Expression::Lambda { .. } => Err(ExpressionError::UnexpectedAbstractSyntaxTree),
+        Expression::Access { target, accessor } => {
+            let target_type = check(target, variables)?;
+            match (accessor, &target_type) {
+                (Accessor::Field(_), Type::Map | Type::Any) => Ok(Type::Any),
+                (Accessor::Index(index), Type::List(_) | Type::Map | Type::Any) => {
+                    let index_type = check(index, variables)?;
+                    let (expected, result) = match &target_type {
+                        Type::List(element) => (Type::Number, element.as_ref().clone()),
+                        Type::Map => (Type::String, Type::Any),
+                        _ => (Type::Any, Type::Any),
+                    };
+                    match expected.accepts(&index_type) {
+                        true => Ok(result),
+                        false => Err(mismatch(
+                            expression,
+                            format!("a {} cannot be indexed by a {}", target_type, index_type),
+                        )),
+                    }
+                }
+                _ => Err(mismatch(
+                    expression,
+                    format!("a {} has no fields or elements", target_type),
+                )),
+            }
+        }
+        Expression::Binary {
+            operator,
+            left,
+            right,
+        } => {
+            let left = check(left, variables)?;
+            let right = check(right, variables)?;
+            binary_type(*operator, &left, &right).ok_or_else(|| {
+                mismatch(
+                    expression,
+                    format!(
+                        "'{}' cannot be applied to a {} and a {}",
+                        operator.symbol(),
+                        left,
+                        right
+                    ),
+                )
+            })
+        }
+        Expression::Prefix { operator, operand } => {
+            let operand = check(operand, variables)?;
+            match operator {
+                PrefixOperator::Not if Type::Boolean.accepts(&operand) => Ok(Type::Boolean),
+                PrefixOperator::Not => Err(mismatch(
+                    expression,
+                    format!("'{}' cannot be applied to a {}", operator.symbol(), operand),
+                )),
+            }
+        }
+        Expression::Postfix { operator, operand } => {
+            check(operand, variables)?;
+            match operator {
+                PostfixOperator::IsNull | PostfixOperator::IsNotNull => Ok(Type::Boolean),
+            }
+        }
+    }
+}
+
+fn mismatch(expression: &Expression, message: String) -> ExpressionError {
+    ExpressionError::TypeMismatch {
+        expression: expression.to_string(),
+        message,
+    }
+}
+
+// Lambdas are checked with their first parameter bound to the elements of the list they are applied
+// to, except for `reduce` whose second parameter is the element and whose first is the running
+// total.
+fn check_call(
+    name: &str,
+    arguments: &[Expression],
+    variables: &HashMap<String, Type>,
+) -> Result<Type, ExpressionError> {
+    let mut types: Vec<Type> = Vec::with_capacity(arguments.len());
+    for argument in arguments {
+        let argument_type = match argument {
+            Expression::Lambda { parameters, body } if function::is_higher_order(name) => {
+                let element = types.first().and_then(Type::element).unwrap_or(Type::Any);
+                let element_position = if name == "reduce" { 1 } else { 0 };
+                let mut scope = variables.clone();
+                for (position, parameter) in parameters.iter().enumerate() {
+                    let parameter_type = match position == element_position {
+                        true => element.clone(),
+                        false => Type::Any,
+                    };
+                    scope.insert(parameter.clone(), parameter_type);
+                }
+                check(body, &scope)?
+            }
+            Expression::Lambda { .. } => {
+                return Err(ExpressionError::InvalidArguments {
+                    function: name.to_owned(),
+                    message: "a lambda can only be passed to a higher-order function".to_owned(),
+                })
+            }
+            argument => check(argument, variables)?,
+        };
+        types.push(argument_type);
+    }
+    function::result_type(name, &types)
+}
+
+fn binary_type(operator: BinaryOperator, left: &Type, right: &Type) -> Option<Type> {
+    use Type::{Any, Boolean, Money, Number, Quantity};
+    match operator {
+        BinaryOperator::Add
+        | BinaryOperator::Subtract
+        | BinaryOperator::Multiply
+        | BinaryOperator::Divide
+        | BinaryOperator::Power => match (operator, left, right) {
+            (_, Any, _) | (_, _, Any) => Some(Any),
+            (_, Number, Number) => Some(Number),
+            (BinaryOperator::Add, Type::String, Type::String) => Some(Type::String),
+            (BinaryOperator::Add | BinaryOperator::Subtract, Money, Money) => Some(Money),
+            (BinaryOperator::Add | BinaryOperator::Subtract, Quantity, Quantity) => Some(Quantity),
+            (BinaryOperator::Multiply, Money, Number)
+            | (BinaryOperator::Multiply, Number, Money) => Some(Money),
+            (BinaryOperator::Divide, Money, Number) => Some(Money),
+            (BinaryOperator::Divide, Money, Money) => Some(Number),
+            (BinaryOperator::Multiply, Quantity, Number)
+            | (BinaryOperator::Multiply, Number, Quantity)
+            | (BinaryOperator::Divide, Quantity, Number)
+            | (BinaryOperator::Power, Quantity, Number) => Some(Quantity),
+            // The units of the result may cancel out, which makes it a plain number.
+            (BinaryOperator::Multiply, Quantity, Quantity)
+            | (BinaryOperator::Divide, Quantity | Number, Quantity) => Some(Any),
+            _ => None,
+        },
+        BinaryOperator::Equal | BinaryOperator::NotEqual => Some(Boolean),
+        BinaryOperator::Less
+        | BinaryOperator::LessOrEqual
+        | BinaryOperator::Greater
+        | BinaryOperator::GreaterOrEqual => match (left, right) {
+            (Any, _) | (_, Any) => Some(Boolean),
+            (Number | Money | Quantity | Type::String | Type::DateTime, _) if left == right => {
+                Some(Boolean)
+            }
+            _ => None,
+        },
+        BinaryOperator::In => right.element().map(|_| Boolean),
+        BinaryOperator::And | BinaryOperator::Or => {
+            match Boolean.accepts(left) && Boolean.accepts(right) {
+                true => Some(Boolean),
+                false => None,
+            }
+        }
+        BinaryOperator::Coalesce => Some(common_pair(left.clone(), right.clone())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_source(source: &str) -> Result<Type, ExpressionError> {
+        let variables = HashMap::from([
+            ("price".to_string(), Type::Money),
+            ("quantity".to_string(), Type::Number),
+            ("weight".to_string(), Type::Quantity),
+            ("name".to_string(), Type::String),
+            ("active".to_string(), Type::Boolean),
+            ("lines".to_string(), Type::List(Box::new(Type::Map))),
+            ("prices".to_string(), Type::List(Box::new(Type::Money))),
+            ("customer".to_string(), Type::Map),
+        ]);
+        check(&source.parse()?, &variables)
+    }
+
+    #[test]
+    fn test_check_result_types() {
+        let cases = [
+            ("price * quantity", "money"),
+            ("price / price", "number"),
+            ("price > EUR 10 and active", "boolean"),
+            ("not active or quantity is null", "boolean"),
+            ("weight * 2 + 1 kg", "quantity"),
+            ("name + \"!\"", "string"),
+            ("sum(prices) ?? EUR 0", "money"),
+            ("map(prices, p -> p * 2)", "list of money"),
+            ("filter(lines, l -> l.price > 0)", "list of map"),
+            ("reduce(prices, (total, p) -> total + p)", "any"),
+            ("count(lines) > 2", "boolean"),
+            ("customer.address.zip", "any"),
+            ("prices[0]", "money"),
+            ("[1, 2.5, null]", "list of number"),
+            ("[1, \"a\"]", "list"),
+            ("format_number(quantity, \"de-DE\")", "string"),
+            ("null", "any"),
+        ];
+        for (source, expected) in cases {
+            let result = check_source(source).unwrap();
+            assert_eq!(result.to_string(), expected, "source = {}", source);
+        }
+    }
+
+    #[test]
+    fn test_check_reports_mismatches() {
+        let sources = [
+            "price + quantity",
+            "price > 10",
+            "weight + 1",
+            "1 - weight",
+            "not quantity",
+            "active and name",
+            "name.first",
+            "prices[\"a\"]",
+            "quantity in 5",
+            "map(prices, p -> p and true)",
+            "sum(x -> x)",
+        ];
+        for source in sources {
+            let result = check_source(source);
+            assert!(result.is_err(), "source = {}", source);
+        }
+        assert!(matches!(
+            check_source("discount * 2"),
+            Err(ExpressionError::UndefinedVariable { .. })
+        ));
+        assert!(matches!(
+            check_source("nope(1)"),
+            Err(ExpressionError::UndefinedFunction { .. })
+        ));
+    }
+
+    #[test]
+    fn test_type_names_round_trip() {
+        let types = [
+            Type::Any,
+            Type::DateTime,
+            Type::List(Box::new(Type::Any)),
+            Type::List(Box::new(Type::List(Box::new(Type::Money)))),
+        ];
+        for value_type in types {
+            assert_eq!(value_type.to_string().parse::<Type>().unwrap(), value_type);
+        }
+        assert!("currency".parse::<Type>().is_err());
+    }
+}